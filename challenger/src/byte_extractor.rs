@@ -0,0 +1,66 @@
+use core::fmt::Debug;
+
+/// Conditions a possibly biased byte stream before it is used for sampling.
+///
+/// See [`SerializingChallenger32::with_extractor`](crate::SerializingChallenger32::with_extractor).
+pub trait ByteExtractor: Debug + Send + Sync {
+    /// Produce one conditioned byte, pulling as many raw bytes from `source` as needed.
+    ///
+    /// Returns `None` if `source` runs dry (returns `None`) before a byte could be produced.
+    fn extract(&self, source: &mut dyn FnMut() -> Option<u8>) -> Option<u8>;
+}
+
+/// The von Neumann extractor.
+///
+/// Raw bits are read in pairs, least significant first. An unequal pair emits its first bit
+/// (`10 -> 1`, `01 -> 0`) and an equal pair is discarded. If the raw bits are independent and
+/// share the same bias, the output bits are exactly uniform.
+///
+/// This is expensive: with unbiased input only one pair in two produces a bit, so each output
+/// byte consumes four raw bytes on average, and more the more biased the input is. Pairs left
+/// over in the last raw byte are discarded. A source which never produces an unequal pair,
+/// e.g. a constant stream, is never accepted and extraction will not terminate.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct VonNeumannExtractor;
+
+impl ByteExtractor for VonNeumannExtractor {
+    fn extract(&self, source: &mut dyn FnMut() -> Option<u8>) -> Option<u8> {
+        let mut output = 0u8;
+        let mut num_bits = 0;
+        while num_bits < 8 {
+            let raw = source()?;
+            for pair in 0..4 {
+                let first = (raw >> (2 * pair)) & 1;
+                let second = (raw >> (2 * pair + 1)) & 1;
+                if first != second {
+                    output |= first << num_bits;
+                    num_bits += 1;
+                    if num_bits == 8 {
+                        break;
+                    }
+                }
+            }
+        }
+        Some(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_von_neumann_extractor() {
+        // 0x99 = 0b1001_1001 reads as the pairs 10, 01, 10, 01 and so emits the bits 1, 0, 1, 0.
+        // 0x00 and 0xff contain only equal pairs and emit nothing.
+        let mut raw = [0x00, 0x99, 0xff, 0x99].into_iter();
+        let extracted = VonNeumannExtractor.extract(&mut || raw.next());
+        assert_eq!(extracted, Some(0b0101_0101));
+    }
+
+    #[test]
+    fn test_von_neumann_extractor_source_runs_dry() {
+        let mut raw = [0x99].into_iter();
+        assert_eq!(VonNeumannExtractor.extract(&mut || raw.next()), None);
+    }
+}
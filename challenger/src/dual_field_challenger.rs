@@ -0,0 +1,166 @@
+use core::convert::Infallible;
+use core::marker::PhantomData;
+
+use p3_field::PrimeField32;
+
+use crate::serializing_challenger::rejection_sample;
+use crate::{CanObserve, CanSample};
+
+/// Type tag observed before each element of the primary field `F`.
+const PRIMARY_TAG: u8 = 0;
+/// Type tag observed before each element of the foreign field `G`.
+const FOREIGN_TAG: u8 = 1;
+
+/// An element of the foreign field `G` of a [`DualFieldChallenger`].
+///
+/// Implementing both `CanObserve<F>` and `CanObserve<G>` for generic `F` and `G` is rejected by
+/// Rust's coherence rules (the two impls overlap when `F = G`), so foreign field elements are
+/// observed and sampled through this wrapper.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Foreign<G>(pub G);
+
+/// Given a challenger that can observe and sample bytes, produces a challenger that is able to
+/// sample and observe field elements of two `PrimeField32`s, a primary field `F` and a foreign
+/// field `G`.
+///
+/// **Observing**:
+/// -  Serializes the field element into a byte array, prefixed by a one byte tag identifying
+///    which of the two fields it belongs to. An element of `F` and an element of `G` with the same
+///    canonical value therefore never produce the same byte stream.
+///
+/// **Sampling**:
+/// -  Samples a field element in a prime field of size `p` by sampling uniformly an element in the
+///    range (0..1 << log_2(p)) and rejecting values `>= p`, using the modulus of the requested
+///    field.
+#[derive(Clone, Debug)]
+pub struct DualFieldChallenger<F, G, Inner> {
+    inner: Inner,
+    _marker: PhantomData<(F, G)>,
+}
+
+impl<F, G, Inner> DualFieldChallenger<F, G, Inner>
+where
+    F: PrimeField32,
+    G: PrimeField32,
+    Inner: CanObserve<u8>,
+{
+    pub const fn new(inner: Inner) -> Self {
+        Self {
+            inner,
+            _marker: PhantomData,
+        }
+    }
+}
+
+fn observe_tagged<Inner: CanObserve<u8>>(inner: &mut Inner, tag: u8, value: u32) {
+    inner.observe(tag);
+    inner.observe_slice(&value.to_le_bytes());
+}
+
+/// Sample an element of the field `K` by rejection sampling bytes drawn from `inner`.
+fn sample_prime_field<K: PrimeField32, Inner: CanSample<u8>>(inner: &mut Inner) -> K {
+    let Ok(value) = rejection_sample(|| Ok::<_, Infallible>(inner.sample_array()));
+    value
+}
+
+impl<F, G, Inner> CanObserve<F> for DualFieldChallenger<F, G, Inner>
+where
+    F: PrimeField32,
+    G: PrimeField32,
+    Inner: CanObserve<u8>,
+{
+    fn observe(&mut self, value: F) {
+        observe_tagged(&mut self.inner, PRIMARY_TAG, value.to_unique_u32());
+    }
+}
+
+impl<F, G, Inner> CanObserve<Foreign<G>> for DualFieldChallenger<F, G, Inner>
+where
+    F: PrimeField32,
+    G: PrimeField32,
+    Inner: CanObserve<u8>,
+{
+    fn observe(&mut self, value: Foreign<G>) {
+        observe_tagged(&mut self.inner, FOREIGN_TAG, value.0.to_unique_u32());
+    }
+}
+
+impl<F, G, Inner> CanSample<F> for DualFieldChallenger<F, G, Inner>
+where
+    F: PrimeField32,
+    G: PrimeField32,
+    Inner: CanSample<u8>,
+{
+    fn sample(&mut self) -> F {
+        sample_prime_field(&mut self.inner)
+    }
+}
+
+impl<F, G, Inner> CanSample<Foreign<G>> for DualFieldChallenger<F, G, Inner>
+where
+    F: PrimeField32,
+    G: PrimeField32,
+    Inner: CanSample<u8>,
+{
+    fn sample(&mut self) -> Foreign<G> {
+        Foreign(sample_prime_field(&mut self.inner))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use p3_baby_bear::BabyBear;
+    use p3_field::PrimeCharacteristicRing;
+    use p3_koala_bear::KoalaBear;
+    use p3_sha256::Sha256;
+
+    use super::*;
+    use crate::HashChallenger;
+
+    type F = BabyBear;
+    type G = KoalaBear;
+    type Chal = DualFieldChallenger<F, G, HashChallenger<u8, Sha256, 32>>;
+
+    fn challenger() -> Chal {
+        Chal::new(HashChallenger::new(vec![], Sha256))
+    }
+
+    #[test]
+    fn test_mixed_field_observations() {
+        let mut a = challenger();
+        a.observe(F::from_u8(1));
+        a.observe(Foreign(G::from_u8(2)));
+        a.observe(F::from_u8(3));
+
+        let mut b = challenger();
+        b.observe(F::from_u8(1));
+        b.observe(Foreign(G::from_u8(2)));
+        b.observe(F::from_u8(3));
+
+        let sample_a: F = a.sample();
+        let sample_b: F = b.sample();
+        assert_eq!(sample_a, sample_b);
+
+        let foreign_a: Foreign<G> = a.sample();
+        let foreign_b: Foreign<G> = b.sample();
+        assert_eq!(foreign_a, foreign_b);
+    }
+
+    #[test]
+    fn test_no_cross_field_confusion() {
+        // Even when both fields are the same, the type tags keep the byte streams apart.
+        type SameFieldChal = DualFieldChallenger<F, F, HashChallenger<u8, Sha256, 32>>;
+
+        let mut primary = SameFieldChal::new(HashChallenger::new(vec![], Sha256));
+        primary.observe(F::from_u8(7));
+
+        let mut foreign = SameFieldChal::new(HashChallenger::new(vec![], Sha256));
+        foreign.observe(Foreign(F::from_u8(7)));
+
+        let primary_sample: F = primary.sample();
+        let foreign_sample: F = foreign.sample();
+        assert_ne!(primary_sample, foreign_sample);
+    }
+}
@@ -1,3 +1,5 @@
+use core::time::Duration;
+
 use p3_field::{Field, PrimeField, PrimeField32, PrimeField64};
 use p3_maybe_rayon::prelude::*;
 use p3_symmetric::CryptographicPermutation;
@@ -5,6 +7,10 @@ use tracing::instrument;
 
 use crate::{CanObserve, CanSampleBits, DuplexChallenger, MultiField32Challenger};
 
+/// Number of candidate witnesses checked when measuring grinding throughput.
+#[cfg(feature = "std")]
+const THROUGHPUT_SAMPLE_SIZE: u32 = 1 << 14;
+
 pub trait GrindingChallenger:
     CanObserve<Self::Witness> + CanSampleBits<usize> + Sync + Clone
 {
@@ -19,6 +25,33 @@ pub trait GrindingChallenger:
     }
 }
 
+/// The mean time needed to find a `bits`-bit proof-of-work witness at the given throughput.
+///
+/// Each candidate witness succeeds with probability `2^-bits`, so on average `2^bits` candidates
+/// are checked before one succeeds. Times too long for a `Duration` saturate at
+/// [`Duration::MAX`].
+pub(crate) fn expected_grind_time(bits: usize, throughput_hashes_per_sec: f64) -> Duration {
+    assert!(bits < (usize::BITS as usize));
+    assert!(throughput_hashes_per_sec > 0.0);
+    let expected_hashes = (1u128 << bits) as f64;
+    Duration::try_from_secs_f64(expected_hashes / throughput_hashes_per_sec)
+        .unwrap_or(Duration::MAX)
+}
+
+/// Time a short grind over `THROUGHPUT_SAMPLE_SIZE` candidate witnesses and return the number of
+/// candidates checked per second.
+#[cfg(feature = "std")]
+pub(crate) fn measure_check_witness_throughput<C: GrindingChallenger>(challenger: &C) -> f64 {
+    let start = std::time::Instant::now();
+    let successes = (0..THROUGHPUT_SAMPLE_SIZE)
+        .into_par_iter()
+        .filter(|&i| challenger.clone().check_witness(1, C::Witness::from_u32(i)))
+        .count();
+    core::hint::black_box(successes);
+    let elapsed = start.elapsed().as_secs_f64().max(f64::MIN_POSITIVE);
+    THROUGHPUT_SAMPLE_SIZE as f64 / elapsed
+}
+
 impl<F, P, const WIDTH: usize, const RATE: usize> GrindingChallenger
     for DuplexChallenger<F, P, WIDTH, RATE>
 where
@@ -33,6 +33,39 @@ where
         }
     }
 
+    /// The hasher used to derive challenges.
+    pub(crate) const fn hasher(&self) -> &H {
+        &self.hasher
+    }
+
+    /// Whether the next sample will invoke the hasher.
+    pub(crate) fn needs_flush(&self) -> bool {
+        self.output_buffer.is_empty()
+    }
+
+    /// A digest of the transcript so far.
+    ///
+    /// Every flush leaves its chaining value at the start of the input buffer, so hashing the
+    /// input buffer covers everything observed. Output not yet consumed from the last flush is
+    /// not reflected.
+    ///
+    /// This is exactly the output the next flush would produce, if nothing more is observed
+    /// first. A digest that must stay apart from the challenges should hash the input buffer
+    /// under a domain tag instead.
+    pub(crate) fn state_digest(&self) -> [T; OUT_LEN] {
+        self.hasher.hash_iter(self.input_buffer.iter().cloned())
+    }
+
+    /// Observed values not yet hashed, starting with the chaining value of the last flush.
+    pub(crate) fn input_buffer(&self) -> &[T] {
+        &self.input_buffer
+    }
+
+    /// Output of the last flush not yet consumed. Samples are taken from the end.
+    pub(crate) fn output_buffer(&self) -> &[T] {
+        &self.output_buffer
+    }
+
     fn flush(&mut self) {
         let inputs = self.input_buffer.drain(..);
         let output = self.hasher.hash_iter(inputs);
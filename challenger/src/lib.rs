@@ -3,21 +3,30 @@
 #![no_std]
 
 extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
 
+mod byte_extractor;
+mod dual_field_challenger;
 mod duplex_challenger;
 mod grinding_challenger;
 mod hash_challenger;
+mod metered_challenger;
 mod multi_field_challenger;
 mod serializing_challenger;
 
 use alloc::vec::Vec;
 use core::array;
 
+pub use byte_extractor::*;
+pub use dual_field_challenger::*;
 pub use duplex_challenger::*;
 pub use grinding_challenger::*;
 pub use hash_challenger::*;
+pub use metered_challenger::*;
 pub use multi_field_challenger::*;
-use p3_field::{BasedVectorSpace, Field};
+use p3_field::{BasedVectorSpace, ExtensionField, Field, PrimeField64, TwoAdicField};
+use p3_util::log2_strict_usize;
 pub use serializing_challenger::*;
 
 pub trait CanObserve<T> {
@@ -45,6 +54,14 @@ pub trait CanSample<T> {
     }
 }
 
+/// A challenger whose sampling can fail, e.g. because it enforces a work limit.
+pub trait CanTrySample<T> {
+    /// The reason sampling failed.
+    type Error;
+
+    fn try_sample(&mut self) -> Result<T, Self::Error>;
+}
+
 pub trait CanSampleBits<T> {
     fn sample_bits(&mut self, bits: usize) -> T;
 }
@@ -59,6 +76,141 @@ pub trait FieldChallenger<F: Field>:
     fn sample_algebra_element<A: BasedVectorSpace<F>>(&mut self) -> A {
         A::from_basis_coefficients_fn(|_| self.sample())
     }
+
+    /// Observe the evaluations of a sumcheck round polynomial, then sample the round challenge.
+    ///
+    /// A round polynomial of degree `d` is sent as `d + 1` evaluations, so the caller is
+    /// responsible for checking `round_poly_evals.len()` against the round's degree bound.
+    fn sample_sumcheck_round<EF: ExtensionField<F>>(&mut self, round_poly_evals: &[EF]) -> EF {
+        for &eval in round_poly_evals {
+            self.observe_algebra_element(eval);
+        }
+        self.sample_algebra_element()
+    }
+
+    /// Sample an out-of-domain point for DEEP-ALI, rejecting any point that is a
+    /// `2^log_domain_size`-th root of unity, i.e. that lies in the trace subgroup.
+    ///
+    /// For a proper extension `EF` the probability of landing in the (base field) subgroup is
+    /// negligible, but the explicit check turns that into a guarantee.
+    fn sample_out_of_domain_point<EF: ExtensionField<F>>(&mut self, log_domain_size: usize) -> EF {
+        loop {
+            let point: EF = self.sample_algebra_element();
+            if point.exp_power_of_2(log_domain_size) != EF::ONE {
+                return point;
+            }
+        }
+    }
+
+    /// Sample a field element and return it together with its low `num_bits` bits, least
+    /// significant first.
+    ///
+    /// The bits are derived deterministically from the single sample; they are not sampled
+    /// independently.
+    fn sample_bit_decomposition(&mut self, num_bits: usize) -> (F, Vec<bool>)
+    where
+        F: PrimeField64,
+    {
+        assert!(num_bits <= 64);
+        let value: F = self.sample();
+        let canonical = value.as_canonical_u64();
+        let bits = (0..num_bits).map(|i| (canonical >> i) & 1 == 1).collect();
+        (value, bits)
+    }
+
+    /// Observe a GKR layer's index and fan-in, then sample the layer challenge.
+    fn sample_gkr_layer_challenge<EF: ExtensionField<F>>(
+        &mut self,
+        layer_index: usize,
+        fan_in: usize,
+    ) -> EF {
+        self.observe(F::from_usize(layer_index));
+        self.observe(F::from_usize(fan_in));
+        self.sample_algebra_element()
+    }
+
+    /// Sample one independent challenge per split of a quotient polynomial split across
+    /// `num_splits` cosets.
+    ///
+    /// These should be sampled after the quotient commitment has been observed, and therefore
+    /// after the constraint-combining challenge that the quotient itself depends on.
+    fn sample_quotient_split_challenges<EF: ExtensionField<F>>(
+        &mut self,
+        num_splits: usize,
+    ) -> Vec<EF> {
+        (0..num_splits)
+            .map(|_| self.sample_algebra_element())
+            .collect()
+    }
+
+    /// Sample a challenge distinct from every challenge in `seen`, resampling on a repeat, and
+    /// record it in `seen`.
+    ///
+    /// The caller keeps `seen` across rounds. A natural repeat has probability about `1/|EF|` per
+    /// pair, so retries are essentially never needed, but distinctness is guaranteed rather than
+    /// merely likely.
+    fn sample_distinct<EF: ExtensionField<F>>(&mut self, seen: &mut Vec<EF>) -> EF {
+        loop {
+            let challenge: EF = self.sample_algebra_element();
+            if !seen.contains(&challenge) {
+                seen.push(challenge);
+                return challenge;
+            }
+        }
+    }
+
+    /// Observe an AIR's index and constraint count, then sample one challenge per constraint for
+    /// that AIR in a batched multi-AIR proof.
+    ///
+    /// Binding the index keeps two AIRs sharing a transcript from reusing each other's
+    /// challenges.
+    fn sample_air_challenges<EF: ExtensionField<F>>(
+        &mut self,
+        air_index: usize,
+        num_constraints: usize,
+    ) -> Vec<EF> {
+        self.observe(F::from_usize(air_index));
+        self.observe(F::from_usize(num_constraints));
+        (0..num_constraints)
+            .map(|_| self.sample_algebra_element())
+            .collect()
+    }
+
+    /// Sample a point of the coset `coset_shift * H`, where `H` is the subgroup of order
+    /// `2^log_blowup` generated by `g = F::two_adic_generator(log_blowup)`.
+    ///
+    /// An index `i` is sampled as `log_blowup` random bits and mapped to the point
+    /// `coset_shift * g^i`, so every point of the coset is equally likely.
+    fn sample_lde_point(&mut self, log_blowup: usize, coset_shift: F) -> F
+    where
+        F: TwoAdicField,
+    {
+        let index = self.sample_bits(log_blowup);
+        coset_shift * F::two_adic_generator(log_blowup).exp_u64(index as u64)
+    }
+
+    /// Observe an oracle's claimed evaluations, prefixed by their number, then sample the
+    /// challenge of an IOP consistency round.
+    fn sample_consistency_challenge<EF: ExtensionField<F>>(&mut self, claimed_evals: &[EF]) -> EF {
+        self.observe(F::from_usize(claimed_evals.len()));
+        for &eval in claimed_evals {
+            self.observe_algebra_element(eval);
+        }
+        self.sample_algebra_element()
+    }
+
+    /// Sample the folding challenges of a single FRI round of the given `arity`.
+    ///
+    /// A round folding by `arity = 2^k` is treated as `k` successive binary folds, so it takes
+    /// `log2(arity)` independent challenges: one for arity 2, two for arity 4, and so on.
+    ///
+    /// # Panics
+    /// Panics if `arity` is not a power of two.
+    fn sample_fri_round_challenges<EF: ExtensionField<F>>(&mut self, arity: usize) -> Vec<EF> {
+        (0..log2_strict_usize(arity))
+            .map(|_| self.sample_algebra_element())
+            .collect()
+    }
 }
 
 impl<C, T> CanObserve<T> for &mut C
@@ -99,6 +251,18 @@ where
     }
 }
 
+impl<C, T> CanTrySample<T> for &mut C
+where
+    C: CanTrySample<T>,
+{
+    type Error = C::Error;
+
+    #[inline(always)]
+    fn try_sample(&mut self) -> Result<T, Self::Error> {
+        (*self).try_sample()
+    }
+}
+
 impl<C, T> CanSampleBits<T> for &mut C
 where
     C: CanSampleBits<T>,
@@ -123,3 +287,170 @@ where
         (*self).sample_algebra_element()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use p3_baby_bear::BabyBear;
+    use p3_field::PrimeCharacteristicRing;
+    use p3_field::extension::BinomialExtensionField;
+    use p3_sha256::Sha256;
+
+    use super::*;
+
+    type F = BabyBear;
+    type EF = BinomialExtensionField<F, 4>;
+    type Chal = SerializingChallenger32<F, HashChallenger<u8, Sha256, 32>>;
+
+    fn challenger() -> Chal {
+        Chal::from_hasher(vec![], Sha256)
+    }
+
+    #[test]
+    fn test_sample_sumcheck_round_depends_on_evals() {
+        let evals = [EF::from_u8(1), EF::from_u8(2), EF::from_u8(3)];
+        let mut other_evals = evals;
+        other_evals[2] = EF::from_u8(4);
+
+        let first = challenger().sample_sumcheck_round(&evals);
+        let second = challenger().sample_sumcheck_round(&evals);
+        let other = challenger().sample_sumcheck_round(&other_evals);
+        assert_eq!(first, second);
+        assert_ne!(first, other);
+    }
+
+    #[test]
+    fn test_sample_out_of_domain_point() {
+        // About one in fifteen BabyBear elements lies in its subgroup of order 2^27, so sampling
+        // base field points will hit some rejections.
+        let log_domain_size = 27;
+        let mut challenger = challenger();
+        for _ in 0..100 {
+            let point: F = challenger.sample_out_of_domain_point(log_domain_size);
+            assert_ne!(point.exp_power_of_2(log_domain_size), F::ONE);
+        }
+        let point: EF = challenger.sample_out_of_domain_point(log_domain_size);
+        assert_ne!(point.exp_power_of_2(log_domain_size), EF::ONE);
+    }
+
+    #[test]
+    fn test_sample_bit_decomposition() {
+        let num_bits = 12;
+        let (value, bits) = challenger().sample_bit_decomposition(num_bits);
+        assert_eq!(bits.len(), num_bits);
+
+        let reconstructed = bits
+            .iter()
+            .enumerate()
+            .fold(0, |acc, (i, &bit)| acc | (u64::from(bit) << i));
+        assert_eq!(
+            reconstructed,
+            value.as_canonical_u64() & ((1 << num_bits) - 1)
+        );
+    }
+
+    #[test]
+    fn test_sample_gkr_layer_challenge_binds_fan_in() {
+        let binary: EF = challenger().sample_gkr_layer_challenge(3, 2);
+        let binary_again: EF = challenger().sample_gkr_layer_challenge(3, 2);
+        let quaternary: EF = challenger().sample_gkr_layer_challenge(3, 4);
+        assert_eq!(binary, binary_again);
+        assert_ne!(binary, quaternary);
+    }
+
+    #[test]
+    fn test_sample_quotient_split_challenges() {
+        let splits: Vec<EF> = challenger().sample_quotient_split_challenges(4);
+        let splits_again: Vec<EF> = challenger().sample_quotient_split_challenges(4);
+        assert_eq!(splits.len(), 4);
+        assert_eq!(splits, splits_again);
+        assert_ne!(splits[0], splits[1]);
+    }
+
+    #[test]
+    fn test_sample_air_challenges_bound_to_index() {
+        let first: Vec<EF> = challenger().sample_air_challenges(0, 3);
+        let first_again: Vec<EF> = challenger().sample_air_challenges(0, 3);
+        let second: Vec<EF> = challenger().sample_air_challenges(1, 3);
+        assert_eq!(first.len(), 3);
+        assert_eq!(first, first_again);
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    fn test_sample_lde_point_in_coset() {
+        let log_blowup = 3;
+        let shift = F::from_u8(31);
+        let mut challenger = challenger();
+        for _ in 0..10 {
+            let point = challenger.sample_lde_point(log_blowup, shift);
+            assert_eq!((point * shift.inverse()).exp_power_of_2(log_blowup), F::ONE);
+        }
+    }
+
+    #[test]
+    fn test_sample_consistency_challenge_depends_on_evals() {
+        let evals = [EF::from_u8(1), EF::from_u8(2)];
+        let other_evals = [EF::from_u8(1), EF::from_u8(3)];
+        let first: EF = challenger().sample_consistency_challenge(&evals);
+        let second: EF = challenger().sample_consistency_challenge(&evals);
+        let truncated: EF = challenger().sample_consistency_challenge(&evals[..1]);
+        let other: EF = challenger().sample_consistency_challenge(&other_evals);
+        assert_eq!(first, second);
+        assert_ne!(first, truncated);
+        assert_ne!(first, other);
+    }
+
+    #[test]
+    fn test_sample_fri_round_challenges() {
+        let mut challenger = challenger();
+        let binary: Vec<EF> = challenger.sample_fri_round_challenges(2);
+        let quaternary: Vec<EF> = challenger.sample_fri_round_challenges(4);
+        assert_eq!(binary.len(), 1);
+        assert_eq!(quaternary.len(), 2);
+        assert_ne!(quaternary[0], quaternary[1]);
+        assert!(!quaternary.contains(&binary[0]));
+    }
+
+    /// A challenger over a "tiny field": it cycles through a fixed list of samples, so repeats
+    /// are certain.
+    struct CyclingChallenger {
+        samples: Vec<F>,
+        next: usize,
+    }
+
+    impl CanObserve<F> for CyclingChallenger {
+        fn observe(&mut self, _value: F) {}
+    }
+
+    impl CanSample<F> for CyclingChallenger {
+        fn sample(&mut self) -> F {
+            let sample = self.samples[self.next % self.samples.len()];
+            self.next += 1;
+            sample
+        }
+    }
+
+    impl CanSampleBits<usize> for CyclingChallenger {
+        fn sample_bits(&mut self, bits: usize) -> usize {
+            self.sample().as_canonical_u64() as usize & ((1 << bits) - 1)
+        }
+    }
+
+    impl FieldChallenger<F> for CyclingChallenger {}
+
+    #[test]
+    fn test_sample_distinct_rejects_repeats() {
+        let mut challenger = CyclingChallenger {
+            samples: vec![F::ONE, F::ONE, F::TWO, F::ONE, F::TWO, F::from_u8(3)],
+            next: 0,
+        };
+        let mut seen = Vec::new();
+        let challenges: Vec<F> = (0..3)
+            .map(|_| challenger.sample_distinct(&mut seen))
+            .collect();
+        assert_eq!(challenges, [F::ONE, F::TWO, F::from_u8(3)]);
+        assert_eq!(seen, challenges);
+    }
+}
@@ -0,0 +1,129 @@
+use p3_symmetric::CryptographicHasher;
+
+use crate::{CanObserve, CanSample, CanTrySample, HashChallenger};
+
+/// Returned once a [`MeteredChallenger`] would exceed its budget of hash invocations.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WorkLimitExceeded {
+    /// The configured maximum number of hash invocations.
+    pub max_hashes: usize,
+}
+
+/// Wraps a [`HashChallenger`], counting the hash invocations it performs and refusing to perform
+/// more than a configured cap.
+///
+/// This bounds the cryptographic work a single, possibly malicious, proof can trigger (e.g.
+/// through pathological rejection sampling): once the cap is hit, sampling returns
+/// [`WorkLimitExceeded`] instead of hashing or panicking. Observing is free, as the inner
+/// challenger only hashes when sampling.
+///
+/// A field challenger on top, such as
+/// [`SerializingChallenger32`](crate::SerializingChallenger32), samples through [`CanTrySample`].
+#[derive(Clone, Debug)]
+pub struct MeteredChallenger<Inner> {
+    inner: Inner,
+    hashes: usize,
+    max_hashes: usize,
+}
+
+impl<Inner> MeteredChallenger<Inner> {
+    pub const fn new(inner: Inner, max_hashes: usize) -> Self {
+        Self {
+            inner,
+            hashes: 0,
+            max_hashes,
+        }
+    }
+
+    /// The number of hash invocations performed so far.
+    pub const fn hashes_used(&self) -> usize {
+        self.hashes
+    }
+}
+
+impl<T, H, const OUT_LEN: usize> CanObserve<T> for MeteredChallenger<HashChallenger<T, H, OUT_LEN>>
+where
+    T: Clone,
+    H: CryptographicHasher<T, [T; OUT_LEN]>,
+{
+    fn observe(&mut self, value: T) {
+        self.inner.observe(value);
+    }
+}
+
+impl<T, H, const OUT_LEN: usize> CanTrySample<T>
+    for MeteredChallenger<HashChallenger<T, H, OUT_LEN>>
+where
+    T: Clone,
+    H: CryptographicHasher<T, [T; OUT_LEN]>,
+{
+    type Error = WorkLimitExceeded;
+
+    fn try_sample(&mut self) -> Result<T, Self::Error> {
+        if self.inner.needs_flush() {
+            if self.hashes >= self.max_hashes {
+                return Err(WorkLimitExceeded {
+                    max_hashes: self.max_hashes,
+                });
+            }
+            self.hashes += 1;
+        }
+        Ok(self.inner.sample())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use p3_baby_bear::BabyBear;
+    use p3_sha256::Sha256;
+
+    use super::*;
+    use crate::SerializingChallenger32;
+
+    fn new_metered(max_hashes: usize) -> MeteredChallenger<HashChallenger<u8, Sha256, 32>> {
+        MeteredChallenger::new(HashChallenger::new(vec![], Sha256), max_hashes)
+    }
+
+    #[test]
+    fn test_hash_cap() {
+        let mut metered = new_metered(1);
+        for _ in 0..32 {
+            assert!(metered.try_sample().is_ok());
+        }
+        assert_eq!(metered.hashes_used(), 1);
+        assert_eq!(
+            metered.try_sample(),
+            Err(WorkLimitExceeded { max_hashes: 1 })
+        );
+    }
+
+    #[test]
+    fn test_observing_is_free_until_sampling() {
+        let mut metered = new_metered(1);
+        assert!(metered.try_sample().is_ok());
+        // Observing invalidates the buffered output, so the next sample needs a fresh hash.
+        metered.observe(1);
+        assert_eq!(metered.hashes_used(), 1);
+        assert!(metered.try_sample().is_err());
+    }
+
+    #[test]
+    fn test_field_sampling_through_metered_challenger() {
+        let metered = new_metered(2);
+        let mut challenger = SerializingChallenger32::<BabyBear, _>::new(metered);
+
+        // Each hash yields 32 bytes, i.e. at most 8 field elements. One of the 16 candidates the
+        // two hashes yield is rejected.
+        let successes = (0..17)
+            .map(|_| CanTrySample::<BabyBear>::try_sample(&mut challenger))
+            .take_while(Result::is_ok)
+            .count();
+        assert_eq!(successes, 15);
+        assert_eq!(
+            CanTrySample::<BabyBear>::try_sample(&mut challenger),
+            Err(WorkLimitExceeded { max_hashes: 2 })
+        );
+    }
+}
@@ -1,16 +1,99 @@
+use alloc::sync::Arc;
 use alloc::vec::Vec;
+use core::convert::Infallible;
 use core::marker::PhantomData;
+use core::time::Duration;
+use core::{array, iter};
 
-use p3_field::{BasedVectorSpace, PrimeField32, PrimeField64};
+use p3_field::{BasedVectorSpace, ExtensionField, PrimeField32, PrimeField64};
 use p3_maybe_rayon::prelude::*;
 use p3_symmetric::{CryptographicHasher, Hash};
 use p3_util::log2_ceil_u64;
 use tracing::instrument;
 
+use crate::grinding_challenger::expected_grind_time;
 use crate::{
-    CanObserve, CanSample, CanSampleBits, FieldChallenger, GrindingChallenger, HashChallenger,
+    ByteExtractor, CanObserve, CanSample, CanSampleBits, CanTrySample, FieldChallenger,
+    GrindingChallenger, HashChallenger,
 };
 
+/// Domain separation tags, observed as a single byte ahead of structured transcript messages.
+mod tag {
+    pub(super) const SAMPLE_COMMITMENT: u8 = 0;
+    pub(super) const COSET_SHIFT: u8 = 1;
+    pub(super) const ROOT_SEED: u8 = 2;
+    pub(super) const INSTANCE_BEGIN: u8 = 3;
+    pub(super) const INSTANCE_END: u8 = 4;
+    pub(super) const INSTANCED_SAMPLE: u8 = 5;
+    pub(super) const BLINDING_COMMITMENT: u8 = 6;
+    pub(super) const SALT: u8 = 7;
+    pub(super) const SORTED_COMMITMENT: u8 = 8;
+    pub(super) const BEACON_VALUE: u8 = 9;
+    pub(super) const BEACON_SIGNATURE: u8 = 10;
+    #[cfg(feature = "debug-transcript")]
+    pub(super) const BLINDED_COMMITMENT: u8 = 11;
+    #[cfg(feature = "debug-transcript")]
+    pub(super) const BLINDING_SEED: u8 = 12;
+    pub(super) const PARALLEL_COMMITMENTS: u8 = 13;
+    pub(super) const SUMCHECK_CLAIM: u8 = 14;
+    pub(super) const PREPROCESSED_COMMITMENT: u8 = 15;
+    pub(super) const OUTPUT_COMMITMENT: u8 = 16;
+    pub(super) const SEGMENT_FINAL_STATE: u8 = 17;
+    pub(super) const SEGMENT_INITIAL_STATE: u8 = 18;
+    #[cfg(feature = "debug-transcript")]
+    pub(super) const PROVER_VERSION: u8 = 19;
+    pub(super) const CHAINED_COMMITMENT: u8 = 20;
+    pub(super) const PROOF_ID: u8 = 21;
+    pub(super) const VERSIONED_COMMITMENT: u8 = 22;
+    pub(super) const DFT_ALGORITHM: u8 = 23;
+}
+
+/// The number of roots hashed into each leaf digest by
+/// [`SerializingChallenger32::observe_commitments_parallel`].
+///
+/// This is part of the transcript format: changing it changes every challenge derived after a
+/// parallel observation.
+pub const COMMITMENT_CHUNK_SIZE: usize = 64;
+
+/// The opening of a commitment produced by [`SerializingChallenger32::commit_next_sample`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SampleOpening<EF> {
+    /// The committed challenge.
+    pub sample: EF,
+    /// The blinding nonce hashed in alongside the challenge.
+    pub blinding: [u8; 32],
+}
+
+/// Returned by [`SerializingChallenger32::observe_and_check_cap`] when a Merkle cap has the wrong
+/// number of entries for the configured cap height.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CapError {
+    /// The configured cap height; the cap should have `2^expected_log_height` entries.
+    pub expected_log_height: usize,
+    /// The number of entries the cap actually has.
+    pub actual_len: usize,
+}
+
+/// Returned by [`SerializingChallenger32::observe_recursion_depth`] when a proof's recursion depth
+/// exceeds the maximum.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DepthError {
+    /// The recursion depth the proof claims.
+    pub depth: u32,
+    /// The maximum recursion depth.
+    pub max_depth: u32,
+}
+
+/// Returned by [`SerializingChallenger32::observe_extension_degree`] when a proof claims a
+/// different extension degree than the verifier's extension field.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FieldMismatch {
+    /// The degree of the verifier's extension field.
+    pub expected_degree: usize,
+    /// The degree the proof claims.
+    pub claimed_degree: u32,
+}
+
 /// Given a challenger that can observe and sample bytes, produces a challenger that is able to
 /// sample and observe field elements of a `PrimeField32`.
 ///
@@ -20,12 +103,24 @@ use crate::{
 /// **Sampling**:
 /// -  Samples a field element in a prime field of size `p` by sampling uniformly an element in the
 ///    range (0..1 << log_2(p)). This avoids modulo bias.
+/// -  If an extractor is set, every byte drawn from the inner challenger is conditioned by it
+///    first.
 #[derive(Clone, Debug)]
 pub struct SerializingChallenger32<F, Inner> {
     inner: Inner,
+    extractor: Option<Arc<dyn ByteExtractor>>,
+    /// Transcript digest recorded by `mark_commitments_done`, from which the proof id is taken.
+    commitment_boundary: Option<[u8; 32]>,
     _marker: PhantomData<F>,
 }
 
+/// A transcript position captured by [`SerializingChallenger32::observe_deferred`], from which an
+/// opening challenge can be sampled later with [`DeferredHandle::sample`].
+#[derive(Clone, Debug)]
+pub struct DeferredHandle<F, Inner> {
+    snapshot: SerializingChallenger32<F, Inner>,
+}
+
 /// Given a challenger that can observe and sample bytes, produces a challenger that is able to
 /// sample and observe field elements of a `PrimeField64` field.
 ///
@@ -45,9 +140,335 @@ impl<F: PrimeField32, Inner: CanObserve<u8>> SerializingChallenger32<F, Inner> {
     pub const fn new(inner: Inner) -> Self {
         Self {
             inner,
+            extractor: None,
+            commitment_boundary: None,
             _marker: PhantomData,
         }
     }
+
+    /// Create a challenger which conditions every byte drawn from `inner` with `extractor` before
+    /// rejection sampling, as a defence against bias in the inner byte stream.
+    ///
+    /// The prover and the verifier must use the same extractor. Extraction costs throughput, as
+    /// each sampled byte consumes several inner bytes; e.g. a
+    /// [`VonNeumannExtractor`](crate::VonNeumannExtractor) consumes at least four on average.
+    pub fn with_extractor<E: ByteExtractor + 'static>(inner: Inner, extractor: E) -> Self {
+        Self {
+            inner,
+            extractor: Some(Arc::new(extractor)),
+            commitment_boundary: None,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Observe an externally supplied public beacon value, bound to its transcript position.
+    ///
+    /// The position and the length of `value` are observed ahead of the beacon bytes, so the
+    /// verifier must inject the same beacon at the same position to reproduce the transcript.
+    pub fn observe_beacon_at(&mut self, position: u64, value: &[u8]) {
+        self.inner.observe_slice(&position.to_le_bytes());
+        self.observe_u32(value.len());
+        self.inner.observe_slice(value);
+    }
+
+    /// Observe the shift of the coset a PCS commits over. This should precede the commitment.
+    ///
+    /// Binding the shift stops a prover from committing over a different coset than claimed.
+    pub fn observe_coset_shift(&mut self, shift: F) {
+        self.inner.observe(tag::COSET_SHIFT);
+        self.observe(shift);
+    }
+
+    /// Observe a single digest of `inputs` in place of the inputs themselves.
+    ///
+    /// This keeps the transcript small when there are many public inputs. The prover and the
+    /// verifier must hash with the identical `hasher`, or their transcripts diverge.
+    pub fn observe_public_input_digest<H>(&mut self, hasher: &H, inputs: &[F])
+    where
+        H: CryptographicHasher<u8, [u8; 32]>,
+    {
+        let digest = hasher.hash_iter(
+            inputs
+                .iter()
+                .flat_map(|input| input.to_unique_u32().to_le_bytes()),
+        );
+        self.inner.observe_slice(&digest);
+    }
+
+    /// Mark the start of instance `instance_id` in a batch transcript.
+    ///
+    /// Everything observed until the matching [`Self::end_instance`] is domain separated by the
+    /// instance id, so identical observations in two instances cannot yield the same challenges.
+    pub fn begin_instance(&mut self, instance_id: u64) {
+        self.inner.observe(tag::INSTANCE_BEGIN);
+        self.inner.observe_slice(&instance_id.to_le_bytes());
+    }
+
+    /// Mark the end of instance `instance_id` in a batch transcript.
+    pub fn end_instance(&mut self, instance_id: u64) {
+        self.inner.observe(tag::INSTANCE_END);
+        self.inner.observe_slice(&instance_id.to_le_bytes());
+    }
+
+    /// Observe `current` as the next entry of an append-only chain of commitments.
+    ///
+    /// A presence flag for `prev` is observed first under a dedicated tag, followed by `prev` if
+    /// present and then `current`, so the chain's structure is bound along with its entries.
+    pub fn observe_chained_commitment<const N: usize>(
+        &mut self,
+        prev: Option<Hash<F, u8, N>>,
+        current: Hash<F, u8, N>,
+    ) {
+        self.inner.observe(tag::CHAINED_COMMITMENT);
+        self.inner.observe(u8::from(prev.is_some()));
+        if let Some(prev) = prev {
+            self.observe(prev);
+        }
+        self.observe(current);
+    }
+
+    /// Observe a zero-knowledge prover's commitment to its random blinding values.
+    ///
+    /// The commitment is observed under a dedicated tag, so it can never be mistaken for a trace
+    /// commitment. It must be observed before any challenge it should influence.
+    pub fn observe_blinding_commitment<const N: usize>(&mut self, blinding_commit: Hash<F, u8, N>) {
+        self.inner.observe(tag::BLINDING_COMMITMENT);
+        self.observe(blinding_commit);
+    }
+
+    /// Observe an AIR's periodic column configuration: the number of periodic columns, then the
+    /// period of each, so the verifier evaluates the same selectors.
+    pub fn observe_periodic_config(&mut self, periods: &[usize]) {
+        self.observe_u32(periods.len());
+        for &period in periods {
+            self.observe_u32(period);
+        }
+    }
+
+    /// Observe `commitment` followed by the number of openings the prover claims for it, so the
+    /// verifier can enforce the count the protocol requires.
+    pub fn observe_opening_count<const N: usize>(
+        &mut self,
+        commitment: Hash<F, u8, N>,
+        num_openings: usize,
+    ) {
+        self.observe(commitment);
+        self.observe_u32(num_openings);
+    }
+
+    /// Observe a per-proof salt, which the verifier reads from the proof and re-observes.
+    ///
+    /// The salt is observed under a dedicated tag and belongs right after the transcript header,
+    /// ahead of any commitment. Proofs with different salts derive different challenges, so a
+    /// replayed or mangled proof does not verify.
+    pub fn observe_salt(&mut self, salt: &[u8; 32]) {
+        self.inner.observe(tag::SALT);
+        self.inner.observe_slice(salt);
+    }
+
+    /// Observe the commitment to a plookup argument's sorted concatenation of witness and table.
+    ///
+    /// The commitment is observed under a dedicated tag. It belongs after the witness commitment
+    /// and before the permutation challenges are sampled, which must depend on it.
+    pub fn observe_sorted_commitment<const N: usize>(&mut self, sorted_commit: Hash<F, u8, N>) {
+        self.inner.observe(tag::SORTED_COMMITMENT);
+        self.observe(sorted_commit);
+    }
+
+    /// Check that `cap` has `2^expected_log_height` entries, then observe its length followed by
+    /// every entry.
+    ///
+    /// A cap of the wrong height is rejected before anything is observed, leaving the transcript
+    /// untouched.
+    pub fn observe_and_check_cap<const N: usize>(
+        &mut self,
+        cap: &[Hash<F, u8, N>],
+        expected_log_height: usize,
+    ) -> Result<(), CapError> {
+        let expected_len = u32::try_from(expected_log_height)
+            .ok()
+            .and_then(|log_height| 1usize.checked_shl(log_height));
+        if expected_len != Some(cap.len()) {
+            return Err(CapError {
+                expected_log_height,
+                actual_len: cap.len(),
+            });
+        }
+        self.observe_u32(cap.len());
+        for &digest in cap {
+            self.observe(digest);
+        }
+        Ok(())
+    }
+
+    /// Observe a public randomness beacon value together with its signature.
+    ///
+    /// The value and the signature are each length prefixed under their own tag. The signature is
+    /// not verified here, only bound, so it cannot be swapped after the fact.
+    pub fn observe_signed_beacon(&mut self, value: &[u8], signature: &[u8]) {
+        self.inner.observe(tag::BEACON_VALUE);
+        self.observe_u32(value.len());
+        self.inner.observe_slice(value);
+        self.inner.observe(tag::BEACON_SIGNATURE);
+        self.observe_u32(signature.len());
+        self.inner.observe_slice(signature);
+    }
+
+    /// Observe a commitment together with the seed of the RNG that blinded it, each under its own
+    /// tag, so an auditor can reproduce the blinding.
+    ///
+    /// Binding the seed reveals the blinding values and so breaks zero-knowledge. This is an
+    /// audit aid only, available behind the `debug-transcript` feature.
+    #[cfg(feature = "debug-transcript")]
+    pub fn observe_blinded_commitment_with_seed<const N: usize>(
+        &mut self,
+        commit: Hash<F, u8, N>,
+        seed: u64,
+    ) {
+        self.inner.observe(tag::BLINDED_COMMITMENT);
+        self.observe(commit);
+        self.inner.observe(tag::BLINDING_SEED);
+        self.inner.observe_slice(&seed.to_le_bytes());
+    }
+
+    /// Observe the per-column degree bounds of an AIR: the number of columns, then the bound of
+    /// each, so the prover cannot claim different bounds to the verifier.
+    pub fn observe_column_bounds(&mut self, bounds: &[usize]) {
+        self.observe_u32(bounds.len());
+        for &bound in bounds {
+            self.observe_u32(bound);
+        }
+    }
+
+    /// Observe the maximum constraint degree the prover claims, which sizes the quotient
+    /// polynomial. This should precede the quotient commitment.
+    pub fn observe_constraint_degree(&mut self, max_degree: usize) {
+        self.observe_u32(max_degree);
+    }
+
+    /// Observe the evaluation points of a multi-point opening: the number of points, then each
+    /// point in order.
+    ///
+    /// The order is bound along with the set, so a permutation of the same points yields a
+    /// different transcript.
+    pub fn observe_eval_points<EF: BasedVectorSpace<F>>(&mut self, points: &[EF]) {
+        self.observe_u32(points.len());
+        for point in points {
+            self.observe_slice(point.as_basis_coefficients_slice());
+        }
+    }
+
+    /// Check `depth` against the maximum recursion depth `max_depth`, then observe it.
+    ///
+    /// A depth above the maximum is rejected before anything is observed.
+    pub fn observe_recursion_depth(
+        &mut self,
+        depth: u32,
+        max_depth: u32,
+    ) -> Result<(), DepthError> {
+        if depth > max_depth {
+            return Err(DepthError { depth, max_depth });
+        }
+        self.inner.observe_slice(&depth.to_le_bytes());
+        Ok(())
+    }
+
+    /// Observe the version of the encoding `commitment` is serialized with under a dedicated tag,
+    /// then the commitment, so commitments under different encodings are never confused.
+    pub fn observe_versioned_commitment<const N: usize>(
+        &mut self,
+        encoding_version: u8,
+        commitment: Hash<F, u8, N>,
+    ) {
+        self.inner.observe(tag::VERSIONED_COMMITMENT);
+        self.inner.observe(encoding_version);
+        self.observe(commitment);
+    }
+
+    /// Observe the sum a sumcheck prover claims, under a dedicated tag that keeps it apart from
+    /// the round polynomials. This must precede the first
+    /// [`sample_sumcheck_round`](FieldChallenger::sample_sumcheck_round).
+    pub fn observe_claimed_sum<EF: BasedVectorSpace<F>>(&mut self, claimed: EF) {
+        self.inner.observe(tag::SUMCHECK_CLAIM);
+        self.observe_slice(claimed.as_basis_coefficients_slice());
+    }
+
+    /// Observe the setup-time commitment to an AIR's preprocessed columns, under a dedicated tag
+    /// that keeps it apart from per-proof trace commitments. This should precede the trace
+    /// commitment.
+    pub fn observe_preprocessed_commitment<const N: usize>(&mut self, prep_commit: Hash<F, u8, N>) {
+        self.inner.observe(tag::PREPROCESSED_COMMITMENT);
+        self.observe(prep_commit);
+    }
+
+    /// Check the extension degree a proof claims against the degree of `EF`, then observe it.
+    ///
+    /// A mismatching degree is rejected before anything is observed, so a proof generated over
+    /// the wrong extension field fails early.
+    pub fn observe_extension_degree<EF: BasedVectorSpace<F>>(
+        &mut self,
+        degree: u32,
+    ) -> Result<(), FieldMismatch> {
+        if degree as usize != EF::DIMENSION {
+            return Err(FieldMismatch {
+                expected_degree: EF::DIMENSION,
+                claimed_degree: degree,
+            });
+        }
+        self.inner.observe_slice(&degree.to_le_bytes());
+        Ok(())
+    }
+
+    /// Observe the commitment to an AIR's public output column, under a dedicated tag, so the
+    /// revealed outputs cannot be substituted. This should precede any challenge.
+    pub fn observe_output_commitment<const N: usize>(&mut self, output_commit: Hash<F, u8, N>) {
+        self.inner.observe(tag::OUTPUT_COMMITMENT);
+        self.observe(output_commit);
+    }
+
+    /// Observe an identifier of the DFT algorithm the prover used, under a dedicated tag, for
+    /// reproducibility audits.
+    ///
+    /// This only binds the choice into the transcript; it has no effect on the DFT itself, whose
+    /// result is the same whichever algorithm computed it.
+    pub fn observe_dft_algorithm(&mut self, algo_id: u8) {
+        self.inner.observe(tag::DFT_ALGORITHM);
+        self.inner.observe(algo_id);
+    }
+
+    /// Observe the boundary between two segments of a continuation proof: the previous segment's
+    /// final state, then the current segment's initial state, each length prefixed under its own
+    /// tag.
+    ///
+    /// The continuation challenge can be sampled once this has been observed, and depends on
+    /// both states, so unrelated segments cannot be stitched together.
+    pub fn observe_segment_boundary(&mut self, prev_final_state: &[F], curr_initial_state: &[F]) {
+        self.inner.observe(tag::SEGMENT_FINAL_STATE);
+        self.observe_u32(prev_final_state.len());
+        self.observe_slice(prev_final_state);
+        self.inner.observe(tag::SEGMENT_INITIAL_STATE);
+        self.observe_u32(curr_initial_state.len());
+        self.observe_slice(curr_initial_state);
+    }
+
+    /// Observe the version string of the prover build, under a diagnostic tag, so a failed
+    /// verification can be traced back to the build that produced the proof.
+    ///
+    /// This changes the transcript: the verifier must observe exactly the same version, so it
+    /// has to know which version to expect, or every proof fails with a binding mismatch. This is
+    /// a diagnostic aid only, available behind the `debug-transcript` feature.
+    #[cfg(feature = "debug-transcript")]
+    pub fn observe_prover_version(&mut self, version: &str) {
+        self.inner.observe(tag::PROVER_VERSION);
+        self.observe_u32(version.len());
+        self.inner.observe_slice(version.as_bytes());
+    }
+
+    /// Observe `value` as a little-endian `u32`.
+    fn observe_u32(&mut self, value: usize) {
+        let value = u32::try_from(value).expect("value should fit in a u32");
+        self.inner.observe_slice(&value.to_le_bytes());
+    }
 }
 
 impl<F, H> SerializingChallenger32<F, HashChallenger<u8, H, 32>>
@@ -58,6 +479,153 @@ where
     pub const fn from_hasher(initial_state: Vec<u8>, hasher: H) -> Self {
         Self::new(HashChallenger::new(initial_state, hasher))
     }
+
+    /// Sample the next challenge and return it together with a commitment to it.
+    ///
+    /// The transcript advances exactly as it would for a plain `sample`, so a verifier which
+    /// samples normally stays in sync. The commitment can later be opened against the challenge
+    /// with [`Self::opens_sample_commitment`].
+    ///
+    /// The commitment is a tagged hash of `blinding` and the challenge. It hides the challenge as
+    /// long as `blinding` is kept secret, provided it is drawn from a source of randomness
+    /// independent of the transcript; otherwise anyone replaying the transcript, or brute forcing
+    /// a small field, could recover the challenge.
+    pub fn commit_next_sample<EF: BasedVectorSpace<F>>(
+        &mut self,
+        blinding: [u8; 32],
+    ) -> (Hash<F, u8, 32>, SampleOpening<EF>) {
+        let opening = SampleOpening {
+            sample: self.sample(),
+            blinding,
+        };
+        (self.commit_to_sample(&opening), opening)
+    }
+
+    /// Check that `commitment` was produced by [`Self::commit_next_sample`] with `opening`.
+    pub fn opens_sample_commitment<EF: BasedVectorSpace<F>>(
+        &self,
+        commitment: Hash<F, u8, 32>,
+        opening: &SampleOpening<EF>,
+    ) -> bool {
+        self.commit_to_sample(opening) == commitment
+    }
+
+    /// Derive `count` challenges from `root` alone, independently of the live transcript.
+    ///
+    /// This is a root-bound derivation, distinct from normal sampling: the challenges are drawn
+    /// from a fresh challenger seeded with `root` under a dedicated tag. The same root always
+    /// yields the same challenges whatever has been observed so far, and the live transcript is
+    /// left untouched.
+    pub fn seeded_samples_from_root<const N: usize, EF: BasedVectorSpace<F>>(
+        &self,
+        root: Hash<F, u8, N>,
+        count: usize,
+    ) -> Vec<EF> {
+        let seed = iter::once(tag::ROOT_SEED).chain(root).collect();
+        let mut seeded = Self::from_hasher(seed, self.inner.hasher().clone());
+        seeded.extractor = self.extractor.clone();
+        CanSample::<EF>::sample_vec(&mut seeded, count)
+    }
+
+    /// Switch to `new_hasher`, seeding it with a digest of the transcript so far.
+    ///
+    /// This changes how every later challenge is derived, so the prover and the verifier must
+    /// both switch to the same hasher at the same point of the transcript. Output buffered by the
+    /// previous hasher but not yet sampled is discarded.
+    pub fn rehash_into<H2>(
+        self,
+        new_hasher: H2,
+    ) -> SerializingChallenger32<F, HashChallenger<u8, H2, 32>>
+    where
+        H2: CryptographicHasher<u8, [u8; 32]>,
+    {
+        let digest = self.inner.state_digest();
+        let mut rehashed = SerializingChallenger32::from_hasher(digest.to_vec(), new_hasher);
+        rehashed.extractor = self.extractor;
+        rehashed.commitment_boundary = self.commitment_boundary;
+        rehashed
+    }
+
+    /// Observe a large batch of commitments as a single two-level tree hash, computed in
+    /// parallel.
+    ///
+    /// The roots are split into chunks of [`COMMITMENT_CHUNK_SIZE`] and each chunk's
+    /// concatenated bytes are hashed into a leaf digest. The concatenated leaf digests are then
+    /// hashed into one combined digest, which is observed under a dedicated tag. This yields a
+    /// different transcript from observing the roots one by one, so the prover and the verifier
+    /// must agree on which of the two they use.
+    pub fn observe_commitments_parallel<const N: usize>(&mut self, roots: &[Hash<F, u8, N>])
+    where
+        H: Sync,
+    {
+        let hasher = self.inner.hasher();
+        let leaves: Vec<[u8; 32]> = roots
+            .par_chunks(COMMITMENT_CHUNK_SIZE)
+            .map(|chunk| hasher.hash_iter(chunk.iter().flat_map(|&root| root)))
+            .collect();
+        let combined = hasher.hash_iter(leaves.into_iter().flatten());
+        self.inner.observe(tag::PARALLEL_COMMITMENTS);
+        self.inner.observe_slice(&combined);
+    }
+
+    /// Declare that the commitment phase is over, recording a digest of the transcript at this
+    /// point as the boundary for [`Self::proof_id`].
+    ///
+    /// The digest is taken under a dedicated tag, so the proof id never coincides with the bytes
+    /// later challenges are drawn from. The challenger cannot tell commitments from openings
+    /// itself, so the prover and the verifier must call this at the same point, once every
+    /// commitment has been observed.
+    pub fn mark_commitments_done(&mut self) {
+        let transcript = self.inner.input_buffer().iter().copied();
+        let digest = self
+            .inner
+            .hasher()
+            .hash_iter(iter::once(tag::PROOF_ID).chain(transcript));
+        self.commitment_boundary = Some(digest);
+    }
+
+    /// A stable identifier for the proof, covering everything observed up to
+    /// [`Self::mark_commitments_done`], i.e. the header and all commitments but no openings.
+    ///
+    /// It is therefore available before the rest of the proof has been generated.
+    ///
+    /// # Panics
+    /// Panics if [`Self::mark_commitments_done`] has not been called.
+    pub fn proof_id(&self) -> [u8; 32] {
+        self.commitment_boundary
+            .expect("mark_commitments_done should be called before proof_id")
+    }
+
+    /// Serialize the inner challenger's exact state, for comparison byte for byte against another
+    /// implementation while debugging.
+    ///
+    /// The dump is the input buffer, then the unconsumed output buffer, each prefixed by its
+    /// length as a little-endian `u64`. The extractor, if any, is not included.
+    pub fn dump_inner_state(&self) -> Vec<u8> {
+        let mut dump = Vec::new();
+        for buffer in [self.inner.input_buffer(), self.inner.output_buffer()] {
+            dump.extend((buffer.len() as u64).to_le_bytes());
+            dump.extend_from_slice(buffer);
+        }
+        dump
+    }
+
+    fn commit_to_sample<EF: BasedVectorSpace<F>>(
+        &self,
+        opening: &SampleOpening<EF>,
+    ) -> Hash<F, u8, 32> {
+        let coeff_bytes = opening
+            .sample
+            .as_basis_coefficients_slice()
+            .iter()
+            .flat_map(|coeff| coeff.to_unique_u32().to_le_bytes());
+        let digest = self.inner.hasher().hash_iter(
+            iter::once(tag::SAMPLE_COMMITMENT)
+                .chain(opening.blinding)
+                .chain(coeff_bytes),
+        );
+        digest.into()
+    }
 }
 
 impl<F: PrimeField32, Inner: CanObserve<u8>> CanObserve<F> for SerializingChallenger32<F, Inner> {
@@ -87,6 +655,109 @@ impl<F: PrimeField32, const N: usize, Inner: CanObserve<u8>> CanObserve<Hash<F,
     }
 }
 
+impl<F, Inner: CanSample<u8>> SerializingChallenger32<F, Inner> {
+    /// Draw `N` bytes from the inner challenger, conditioned by the extractor if one is set.
+    fn sample_bytes<const N: usize>(&mut self) -> [u8; N] {
+        self.sample_bytes_traced(None)
+    }
+
+    /// Like `sample_bytes`, additionally recording every byte drawn from the inner challenger,
+    /// before extraction, in `trace`.
+    fn sample_bytes_traced<const N: usize>(&mut self, mut trace: Option<&mut Vec<u8>>) -> [u8; N] {
+        let inner = &mut self.inner;
+        let mut next_raw = || {
+            let raw = inner.sample();
+            if let Some(trace) = trace.as_deref_mut() {
+                trace.push(raw);
+            }
+            raw
+        };
+        match &self.extractor {
+            Some(extractor) => array::from_fn(|_| {
+                extractor
+                    .extract(&mut || Some(next_raw()))
+                    .expect("the inner challenger never runs dry")
+            }),
+            None => array::from_fn(|_| next_raw()),
+        }
+    }
+}
+
+impl<F: PrimeField32, Inner: CanSample<u8>> SerializingChallenger32<F, Inner> {
+    /// Sample `N` challenges, also returning every byte drawn from the inner challenger to
+    /// produce them, including those of rejected candidates.
+    ///
+    /// The challenges are exactly those `sample_array` would return. This is meant for building
+    /// conformance vectors which pin down a reference implementation's byte consumption as well
+    /// as its outputs.
+    #[cfg(any(test, feature = "test-utils"))]
+    pub fn sample_array_with_trace<const N: usize, EF: BasedVectorSpace<F>>(
+        &mut self,
+    ) -> ([EF; N], Vec<u8>) {
+        let mut trace = Vec::new();
+        let samples = array::from_fn(|_| {
+            EF::from_basis_coefficients_fn(|_| self.sample_base(Some(&mut trace)))
+        });
+        (samples, trace)
+    }
+
+    /// Sample a base field element, recording the bytes drawn in `trace` if given.
+    fn sample_base(&mut self, mut trace: Option<&mut Vec<u8>>) -> F {
+        let Ok(value) = rejection_sample(|| {
+            Ok::<_, Infallible>(self.sample_bytes_traced(trace.as_deref_mut()))
+        });
+        value
+    }
+}
+
+/// Perform rejection sampling over the uniform range (0..log2_ceil(p)), drawing each candidate
+/// from `next_bytes` and failing as soon as it does.
+pub(crate) fn rejection_sample<F: PrimeField32, E>(
+    mut next_bytes: impl FnMut() -> Result<[u8; 4], E>,
+) -> Result<F, E> {
+    let modulus = F::ORDER_U32;
+    let log_size = log2_ceil_u64(F::ORDER_U64);
+    // We use u64 to avoid overflow in the case that log_size = 32.
+    let pow_of_two_bound = ((1u64 << log_size) - 1) as u32;
+    loop {
+        let value = u32::from_le_bytes(next_bytes()?);
+        let value = value & pow_of_two_bound;
+        if value < modulus {
+            return Ok(unsafe {
+                // This is safe as value < F::ORDER_U32.
+                F::from_canonical_unchecked(value)
+            });
+        }
+    }
+}
+
+impl<F, Inner: CanTrySample<u8>> SerializingChallenger32<F, Inner> {
+    /// Fallible counterpart of `sample_bytes`, failing as soon as the inner challenger does.
+    fn try_sample_bytes<const N: usize>(&mut self) -> Result<[u8; N], Inner::Error> {
+        let mut bytes = [0u8; N];
+        for byte in &mut bytes {
+            *byte = match &self.extractor {
+                Some(extractor) => {
+                    let mut error = None;
+                    let mut source = || match self.inner.try_sample() {
+                        Ok(raw) => Some(raw),
+                        Err(err) => {
+                            error = Some(err);
+                            None
+                        }
+                    };
+                    match extractor.extract(&mut source) {
+                        Some(extracted) => extracted,
+                        None => return Err(error.expect("extraction only fails with its source")),
+                    }
+                }
+                None => self.inner.try_sample()?,
+            };
+        }
+        Ok(bytes)
+    }
+}
+
 impl<F, EF, Inner> CanSample<EF> for SerializingChallenger32<F, Inner>
 where
     F: PrimeField32,
@@ -94,22 +765,32 @@ where
     Inner: CanSample<u8>,
 {
     fn sample(&mut self) -> EF {
-        let modulus = F::ORDER_U32;
-        let log_size = log2_ceil_u64(F::ORDER_U64);
-        // We use u64 to avoid overflow in the case that log_size = 32.
-        let pow_of_two_bound = ((1u64 << log_size) - 1) as u32;
-        // Perform rejection sampling over the uniform range (0..log2_ceil(p))
-        let sample_base = |inner: &mut Inner| loop {
-            let value = u32::from_le_bytes(inner.sample_array());
-            let value = value & pow_of_two_bound;
-            if value < modulus {
-                return unsafe {
-                    // This is safe as value < F::ORDER_U32.
-                    F::from_canonical_unchecked(value)
-                };
+        EF::from_basis_coefficients_fn(|_| self.sample_base(None))
+    }
+}
+
+impl<F, EF, Inner> CanTrySample<EF> for SerializingChallenger32<F, Inner>
+where
+    F: PrimeField32,
+    EF: BasedVectorSpace<F>,
+    Inner: CanTrySample<u8>,
+{
+    type Error = Inner::Error;
+
+    fn try_sample(&mut self) -> Result<EF, Self::Error> {
+        // Stop as soon as the inner challenger refuses to produce more bytes; the coefficients
+        // filled in after that are placeholders, discarded along with the sample.
+        let mut error = None;
+        let sample = EF::from_basis_coefficients_fn(|_| {
+            if error.is_some() {
+                return F::ZERO;
             }
-        };
-        EF::from_basis_coefficients_fn(|_| sample_base(&mut self.inner))
+            rejection_sample(|| self.try_sample_bytes()).unwrap_or_else(|err| {
+                error = Some(err);
+                F::ZERO
+            })
+        });
+        error.map_or(Ok(sample), Err)
     }
 }
 
@@ -122,11 +803,165 @@ where
         assert!(bits < (usize::BITS as usize));
         // Limiting the number of bits to the field size
         assert!((1 << bits) <= F::ORDER_U64 as usize);
-        let rand_usize = u32::from_le_bytes(self.inner.sample_array()) as usize;
+        let rand_usize = u32::from_le_bytes(self.sample_bytes()) as usize;
         rand_usize & ((1 << bits) - 1)
     }
 }
 
+impl<F, Inner> SerializingChallenger32<F, Inner>
+where
+    F: PrimeField32,
+    Inner: CanObserve<u8> + CanSample<u8>,
+{
+    /// Observe `commitment`, then sample the next challenge.
+    ///
+    /// Fusing the two guarantees the challenge is never drawn before the commitment it should
+    /// depend on has been observed.
+    pub fn observe_then_sample<const N: usize, EF: BasedVectorSpace<F>>(
+        &mut self,
+        commitment: Hash<F, u8, N>,
+    ) -> EF {
+        self.observe(commitment);
+        self.sample()
+    }
+
+    /// Sample a base field challenge and embed it into `EF`.
+    ///
+    /// The result lies in the base field, the fixed field of the Frobenius map, so it is drawn
+    /// from only `|F|` possibilities rather than `|EF|`. This is strictly weaker randomness than
+    /// sampling `EF` directly and should only be used where the protocol requires base field
+    /// challenges.
+    pub fn sample_base_in_extension<EF: ExtensionField<F>>(&mut self) -> EF {
+        let base: F = self.sample();
+        EF::from(base)
+    }
+
+    /// Observe the accumulator commitment, then the new instance's commitment, then sample the
+    /// challenge folding the new instance into the accumulator.
+    pub fn sample_accumulation_challenge<const N: usize, EF: BasedVectorSpace<F>>(
+        &mut self,
+        acc_commit: Hash<F, u8, N>,
+        new_commit: Hash<F, u8, N>,
+    ) -> EF {
+        self.observe(acc_commit);
+        self.observe_then_sample(new_commit)
+    }
+
+    /// Observe the witness commitment of a grand-product argument, then sample `(beta, gamma)`,
+    /// in that order.
+    pub fn sample_permutation_challenges<const N: usize, EF: BasedVectorSpace<F>>(
+        &mut self,
+        witness_commit: Hash<F, u8, N>,
+    ) -> (EF, EF) {
+        let beta = self.observe_then_sample(witness_commit);
+        let gamma = self.sample();
+        (beta, gamma)
+    }
+
+    /// Observe the commitment of every round of a multi-round PCS, in order, then sample the
+    /// challenge batching openings across all rounds.
+    pub fn sample_cross_round_batch_challenge<const N: usize, EF: BasedVectorSpace<F>>(
+        &mut self,
+        round_commits: &[Hash<F, u8, N>],
+    ) -> EF {
+        for &commit in round_commits {
+            self.observe(commit);
+        }
+        self.sample()
+    }
+
+    /// Observe the commitment of a Basefold folding round, then sample that round's folding
+    /// challenge.
+    pub fn sample_basefold_round<const N: usize, EF: BasedVectorSpace<F>>(
+        &mut self,
+        round_commit: Hash<F, u8, N>,
+    ) -> EF {
+        self.observe_then_sample(round_commit)
+    }
+
+    /// Observe `commitment` and return a handle to the transcript position right after it.
+    ///
+    /// Observation continues as normal, and the opening challenge for `commitment` can be
+    /// sampled later with [`DeferredHandle::sample`], as if it had been sampled right away.
+    pub fn observe_deferred<const N: usize>(
+        &mut self,
+        commitment: Hash<F, u8, N>,
+    ) -> DeferredHandle<F, Inner>
+    where
+        Inner: Clone,
+    {
+        self.observe(commitment);
+        DeferredHandle {
+            snapshot: self.clone(),
+        }
+    }
+
+    /// Run the two-phase transcript of a randomized AIR: sample the `EF` challenge the auxiliary
+    /// columns are built from, observe `aux_commit`, then sample and return the next challenge.
+    ///
+    /// Only the second challenge is returned. The auxiliary columns, and so `aux_commit`, depend
+    /// on the first one, so obtain it beforehand with [`Self::peek_sample`].
+    pub fn sample_then_observe_aux<const N: usize, EF: BasedVectorSpace<F>>(
+        &mut self,
+        aux_commit: Hash<F, u8, N>,
+    ) -> EF {
+        let _aux_challenge: EF = self.sample();
+        self.observe_then_sample(aux_commit)
+    }
+
+    /// Observe the number of children of an aggregation tree node, then each child's proof
+    /// digest in order, then sample the challenge combining the children.
+    pub fn sample_aggregation_node_challenge<EF: BasedVectorSpace<F>>(
+        &mut self,
+        child_digests: &[[u8; 32]],
+    ) -> EF {
+        self.observe_u32(child_digests.len());
+        for digest in child_digests {
+            self.inner.observe_slice(digest);
+        }
+        self.sample()
+    }
+
+    /// Return the challenge the next call to `sample` will produce, without advancing the
+    /// transcript.
+    ///
+    /// This is a one-step lookahead computed on a clone of the current state; it does not affect
+    /// any subsequent sampling.
+    pub fn peek_sample<EF: BasedVectorSpace<F>>(&self) -> EF
+    where
+        Inner: Clone,
+    {
+        self.clone().sample()
+    }
+
+    /// Sample a challenge specific to `instance`, without advancing the shared transcript.
+    ///
+    /// The instance index is mixed into a clone of the current state under a dedicated tag, so
+    /// every instance gets an independent challenge derived from the shared entropy, and asking
+    /// for the same instance again reproduces it.
+    pub fn sample_instanced<EF: BasedVectorSpace<F>>(&self, instance: u64) -> EF
+    where
+        Inner: Clone,
+    {
+        let mut instanced = self.clone();
+        instanced.inner.observe(tag::INSTANCED_SAMPLE);
+        instanced.inner.observe_slice(&instance.to_le_bytes());
+        instanced.sample()
+    }
+}
+
+impl<F: PrimeField32, Inner: CanSample<u8>> DeferredHandle<F, Inner> {
+    /// Sample the opening challenge for the commitment this handle was created for.
+    ///
+    /// The challenge is drawn at the captured position, so it matches what sampling right after
+    /// the commitment would have produced. The live transcript never sees it, so the verifier
+    /// must defer the same challenges.
+    pub fn sample<EF: BasedVectorSpace<F>>(self) -> EF {
+        let mut snapshot = self.snapshot;
+        snapshot.sample()
+    }
+}
+
 impl<F, Inner> GrindingChallenger for SerializingChallenger32<F, Inner>
 where
     F: PrimeField32,
@@ -158,6 +993,32 @@ where
 {
 }
 
+impl<F, Inner> SerializingChallenger32<F, Inner> {
+    /// The expected time [`grind`](GrindingChallenger::grind) takes to find a `bits`-bit witness,
+    /// given a throughput in checked witnesses per second.
+    ///
+    /// This is `2^bits / throughput_hashes_per_sec`. It is a mean, not a worst case: the number of
+    /// witnesses checked before one succeeds is geometrically distributed.
+    pub fn grind_cost_estimate(bits: usize, throughput_hashes_per_sec: f64) -> Duration {
+        expected_grind_time(bits, throughput_hashes_per_sec)
+    }
+}
+
+impl<F, Inner> SerializingChallenger32<F, Inner>
+where
+    F: PrimeField32,
+    Inner: CanSample<u8> + CanObserve<u8> + Clone + Send + Sync,
+{
+    /// Estimate the grinding throughput of this challenger, in checked witnesses per second, by
+    /// timing a short grind from the current state.
+    ///
+    /// The result can be passed to [`Self::grind_cost_estimate`].
+    #[cfg(feature = "std")]
+    pub fn measure_grind_throughput(&self) -> f64 {
+        crate::grinding_challenger::measure_check_witness_throughput(self)
+    }
+}
+
 impl<F: PrimeField64, Inner: CanObserve<u8>> SerializingChallenger64<F, Inner> {
     pub const fn new(inner: Inner) -> Self {
         Self {
@@ -275,3 +1136,823 @@ where
     Inner: CanSample<u8> + CanObserve<u8> + Clone + Send + Sync,
 {
 }
+
+impl<F, Inner> SerializingChallenger64<F, Inner> {
+    /// The expected time [`grind`](GrindingChallenger::grind) takes to find a `bits`-bit witness,
+    /// as for [`SerializingChallenger32::grind_cost_estimate`].
+    pub fn grind_cost_estimate(bits: usize, throughput_hashes_per_sec: f64) -> Duration {
+        expected_grind_time(bits, throughput_hashes_per_sec)
+    }
+}
+
+impl<F, Inner> SerializingChallenger64<F, Inner>
+where
+    F: PrimeField64,
+    Inner: CanSample<u8> + CanObserve<u8> + Clone + Send + Sync,
+{
+    /// Estimate the grinding throughput of this challenger, in checked witnesses per second, by
+    /// timing a short grind from the current state.
+    ///
+    /// The result can be passed to [`Self::grind_cost_estimate`].
+    #[cfg(feature = "std")]
+    pub fn measure_grind_throughput(&self) -> f64 {
+        crate::grinding_challenger::measure_check_witness_throughput(self)
+    }
+}
+
+/// Checks that the zero-knowledge breaking audit methods only exist with the `debug-transcript`
+/// feature: without it this example must fail to compile, and with it, it must run.
+#[cfg_attr(not(feature = "debug-transcript"), doc = "```compile_fail")]
+#[cfg_attr(feature = "debug-transcript", doc = "```")]
+/// use p3_baby_bear::BabyBear;
+/// use p3_challenger::SerializingChallenger32;
+/// use p3_sha256::Sha256;
+/// use p3_symmetric::Hash;
+///
+/// let mut challenger = SerializingChallenger32::<BabyBear, _>::from_hasher(vec![], Sha256);
+/// let commit = Hash::<BabyBear, u8, 32>::from([0; 32]);
+/// challenger.observe_blinded_commitment_with_seed(commit, 0);
+/// ```
+#[cfg(doctest)]
+struct BlindingSeedIsDebugOnly;
+
+/// Checks that prover version binding only exists with the `debug-transcript` feature: without
+/// it this example must fail to compile, and with it, it must run.
+#[cfg_attr(not(feature = "debug-transcript"), doc = "```compile_fail")]
+#[cfg_attr(feature = "debug-transcript", doc = "```")]
+/// use p3_baby_bear::BabyBear;
+/// use p3_challenger::SerializingChallenger32;
+/// use p3_sha256::Sha256;
+///
+/// let mut challenger = SerializingChallenger32::<BabyBear, _>::from_hasher(vec![], Sha256);
+/// challenger.observe_prover_version("0.1.0");
+/// ```
+#[cfg(doctest)]
+struct ProverVersionIsDebugOnly;
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use p3_baby_bear::BabyBear;
+    use p3_field::PrimeCharacteristicRing;
+    use p3_field::extension::BinomialExtensionField;
+    use p3_sha256::Sha256;
+
+    use super::*;
+    use crate::VonNeumannExtractor;
+
+    type F = BabyBear;
+    type EF = BinomialExtensionField<F, 4>;
+    type Chal = SerializingChallenger32<F, HashChallenger<u8, Sha256, 32>>;
+
+    fn challenger() -> Chal {
+        Chal::from_hasher(vec![], Sha256)
+    }
+
+    /// SHA-256 with a fixed prefix byte, standing in for a second, unrelated hasher.
+    #[derive(Clone)]
+    struct PrefixedSha256;
+
+    impl CryptographicHasher<u8, [u8; 32]> for PrefixedSha256 {
+        fn hash_iter<I>(&self, input: I) -> [u8; 32]
+        where
+            I: IntoIterator<Item = u8>,
+        {
+            Sha256.hash_iter(iter::once(0xff).chain(input))
+        }
+    }
+
+    /// Run `observations` on a fresh challenger and return the challenge sampled afterwards.
+    fn sample_after(observations: impl FnOnce(&mut Chal)) -> F {
+        let mut challenger = challenger();
+        observations(&mut challenger);
+        challenger.sample()
+    }
+
+    #[test]
+    fn test_commit_next_sample_opens() {
+        let mut prover = challenger();
+        prover.observe(F::from_u8(1));
+        let mut verifier = prover.clone();
+
+        let (commitment, opening) = prover.commit_next_sample::<EF>([7; 32]);
+        assert!(prover.opens_sample_commitment(commitment, &opening));
+        let wrong_sample = SampleOpening {
+            sample: opening.sample + EF::ONE,
+            ..opening
+        };
+        assert!(!prover.opens_sample_commitment(commitment, &wrong_sample));
+        let wrong_blinding = SampleOpening {
+            blinding: [8; 32],
+            ..opening
+        };
+        assert!(!prover.opens_sample_commitment(commitment, &wrong_blinding));
+
+        // The same challenge under a different blinding nonce gets an unrelated commitment.
+        let (reblinded, _) = verifier.clone().commit_next_sample::<EF>([8; 32]);
+        assert_ne!(commitment, reblinded);
+
+        // A verifier sampling normally stays in sync with the prover.
+        let verifier_sample: EF = verifier.sample();
+        assert_eq!(opening.sample, verifier_sample);
+        let next_prover: F = prover.sample();
+        let next_verifier: F = verifier.sample();
+        assert_eq!(next_prover, next_verifier);
+    }
+
+    #[test]
+    fn test_observe_beacon_at_binds_position() {
+        let beacon = [0xab; 16];
+        let at_three = sample_after(|c| c.observe_beacon_at(3, &beacon));
+        let at_three_again = sample_after(|c| c.observe_beacon_at(3, &beacon));
+        let at_four = sample_after(|c| c.observe_beacon_at(4, &beacon));
+        assert_eq!(at_three, at_three_again);
+        assert_ne!(at_three, at_four);
+    }
+
+    #[test]
+    fn test_grind_cost_estimate() {
+        // The estimate does not depend on the inner challenger, so any type will do.
+        type Unbounded = SerializingChallenger32<F, ()>;
+        assert_eq!(
+            Unbounded::grind_cost_estimate(10, 1024.0),
+            Duration::from_secs(1)
+        );
+        assert_eq!(
+            Unbounded::grind_cost_estimate(20, 1024.0),
+            Duration::from_secs(1024)
+        );
+        // Estimates beyond what a `Duration` can hold saturate rather than panic.
+        assert_eq!(Unbounded::grind_cost_estimate(63, 1e-3), Duration::MAX);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_measure_grind_throughput() {
+        let throughput = challenger().measure_grind_throughput();
+        assert!(throughput > 0.0);
+        assert!(Chal::grind_cost_estimate(8, throughput) > Duration::ZERO);
+    }
+
+    #[test]
+    fn test_observe_then_sample_matches_separate_calls() {
+        let commitment = Hash::<F, u8, 32>::from([7; 32]);
+
+        let mut fused = challenger();
+        let fused_sample: EF = fused.observe_then_sample(commitment);
+
+        let mut separate = challenger();
+        separate.observe(commitment);
+        let separate_sample: EF = separate.sample();
+
+        assert_eq!(fused_sample, separate_sample);
+    }
+
+    #[test]
+    fn test_peek_sample_does_not_advance() {
+        let mut challenger = challenger();
+        challenger.observe(F::from_u8(3));
+        let mut reference = challenger.clone();
+
+        let peeked: EF = challenger.peek_sample();
+        let sampled: EF = challenger.sample();
+        assert_eq!(peeked, sampled);
+
+        let reference_first: EF = reference.sample();
+        assert_eq!(sampled, reference_first);
+        let next: EF = challenger.sample();
+        let reference_next: EF = reference.sample();
+        assert_eq!(next, reference_next);
+    }
+
+    #[test]
+    fn test_observe_coset_shift() {
+        let shift = F::from_u8(31);
+        let shifted = sample_after(|c| c.observe_coset_shift(shift));
+        let unshifted = sample_after(|c| c.observe_coset_shift(F::ONE));
+        let untagged = sample_after(|c| c.observe(shift));
+        assert_ne!(shifted, unshifted);
+        assert_ne!(shifted, untagged);
+    }
+
+    #[test]
+    fn test_seeded_samples_from_root() {
+        let root = Hash::<F, u8, 32>::from([1; 32]);
+        let other_root = Hash::<F, u8, 32>::from([2; 32]);
+
+        let fresh = challenger();
+        let mut advanced = challenger();
+        advanced.observe(F::from_u8(9));
+        let _: F = advanced.sample();
+
+        let samples: Vec<EF> = fresh.seeded_samples_from_root(root, 4);
+        assert_eq!(samples.len(), 4);
+        // The derivation ignores the live transcript.
+        assert_eq!(
+            samples,
+            advanced.seeded_samples_from_root::<32, EF>(root, 4)
+        );
+        assert_ne!(
+            samples,
+            fresh.seeded_samples_from_root::<32, EF>(other_root, 4)
+        );
+    }
+
+    #[test]
+    fn test_observe_public_input_digest() {
+        let inputs: Vec<F> = (0..8).map(F::from_u8).collect();
+        let baseline = sample_after(|c| c.observe_public_input_digest(&Sha256, &inputs));
+
+        for i in 0..inputs.len() {
+            let mut modified = inputs.clone();
+            modified[i] += F::ONE;
+            let sample = sample_after(|c| c.observe_public_input_digest(&Sha256, &modified));
+            assert_ne!(baseline, sample);
+        }
+    }
+
+    #[test]
+    fn test_instances_are_domain_separated() {
+        let run_instance = |instance_id| {
+            sample_after(|c| {
+                c.begin_instance(instance_id);
+                c.observe(F::from_u8(5));
+                c.end_instance(instance_id);
+            })
+        };
+        assert_eq!(run_instance(0), run_instance(0));
+        assert_ne!(run_instance(0), run_instance(1));
+    }
+
+    #[test]
+    fn test_rehash_into_is_deterministic() {
+        let mut first = challenger();
+        first.observe(F::from_u8(1));
+        let second = first.clone();
+
+        let mut first = first.rehash_into(PrefixedSha256);
+        let mut second = second.rehash_into(PrefixedSha256);
+        let first_sample: EF = first.sample();
+        let second_sample: EF = second.sample();
+        assert_eq!(first_sample, second_sample);
+
+        // Switching hashers changes the derivation.
+        let mut unswitched = challenger();
+        unswitched.observe(F::from_u8(1));
+        let unswitched_sample: EF = unswitched.sample();
+        assert_ne!(first_sample, unswitched_sample);
+    }
+
+    #[test]
+    fn test_observe_chained_commitment() {
+        let genesis = Hash::<F, u8, 32>::from([1; 32]);
+        let forged = Hash::<F, u8, 32>::from([2; 32]);
+        let current = Hash::<F, u8, 32>::from([3; 32]);
+
+        let chained = sample_after(|c| c.observe_chained_commitment(Some(genesis), current));
+        let chained_again = sample_after(|c| c.observe_chained_commitment(Some(genesis), current));
+        let wrong_prev = sample_after(|c| c.observe_chained_commitment(Some(forged), current));
+        let unchained = sample_after(|c| c.observe_chained_commitment(None, current));
+        assert_eq!(chained, chained_again);
+        assert_ne!(chained, wrong_prev);
+        assert_ne!(chained, unchained);
+
+        // A short `prev` spelling a coset shift is not mistaken for a coset shift message.
+        let shift = F::from_u8(31);
+        let prev = Hash::<F, u8, 4>::from(shift.to_unique_u32().to_le_bytes());
+        let short_current = Hash::<F, u8, 4>::from([3; 4]);
+        let short_chained =
+            sample_after(|c| c.observe_chained_commitment(Some(prev), short_current));
+        let shifted = sample_after(|c| {
+            c.observe_coset_shift(shift);
+            c.observe(short_current);
+        });
+        assert_ne!(short_chained, shifted);
+    }
+
+    #[test]
+    fn test_sample_instanced() {
+        let mut challenger = challenger();
+        challenger.observe(F::from_u8(2));
+        let reference = challenger.clone();
+
+        let first: EF = challenger.sample_instanced(0);
+        let second: EF = challenger.sample_instanced(1);
+        let first_again: EF = challenger.sample_instanced(0);
+        assert_ne!(first, second);
+        assert_eq!(first, first_again);
+
+        // The shared transcript has not advanced.
+        let sample: EF = challenger.peek_sample();
+        assert_eq!(sample, reference.peek_sample::<EF>());
+    }
+
+    #[test]
+    fn test_observe_blinding_commitment() {
+        let blinding = Hash::<F, u8, 32>::from([4; 32]);
+        let other_blinding = Hash::<F, u8, 32>::from([5; 32]);
+
+        let blinded = sample_after(|c| c.observe_blinding_commitment(blinding));
+        let other = sample_after(|c| c.observe_blinding_commitment(other_blinding));
+        let untagged = sample_after(|c| c.observe(blinding));
+        assert_ne!(blinded, other);
+        assert_ne!(blinded, untagged);
+    }
+
+    #[test]
+    fn test_with_extractor_is_consistent() {
+        let extracting =
+            || Chal::with_extractor(HashChallenger::new(vec![], Sha256), VonNeumannExtractor);
+        let mut prover = extracting();
+        let mut verifier = extracting();
+        let mut plain = challenger();
+        for challenger in [&mut prover, &mut verifier] {
+            challenger.observe(F::from_u8(6));
+        }
+        plain.observe(F::from_u8(6));
+
+        let prover_samples = CanSample::<EF>::sample_vec(&mut prover, 3);
+        let verifier_samples = CanSample::<EF>::sample_vec(&mut verifier, 3);
+        let plain_samples = CanSample::<EF>::sample_vec(&mut plain, 3);
+        assert_eq!(prover_samples, verifier_samples);
+        assert_ne!(prover_samples, plain_samples);
+        assert_eq!(prover.sample_bits(10), verifier.sample_bits(10));
+    }
+
+    #[test]
+    fn test_observe_periodic_config() {
+        let config = sample_after(|c| c.observe_periodic_config(&[2, 4, 8]));
+        let config_again = sample_after(|c| c.observe_periodic_config(&[2, 4, 8]));
+        let other_period = sample_after(|c| c.observe_periodic_config(&[2, 4, 16]));
+        let fewer_columns = sample_after(|c| c.observe_periodic_config(&[2, 4]));
+        assert_eq!(config, config_again);
+        assert_ne!(config, other_period);
+        assert_ne!(config, fewer_columns);
+    }
+
+    #[test]
+    fn test_observe_opening_count() {
+        let commitment = Hash::<F, u8, 32>::from([8; 32]);
+        let two = sample_after(|c| c.observe_opening_count(commitment, 2));
+        let two_again = sample_after(|c| c.observe_opening_count(commitment, 2));
+        let one = sample_after(|c| c.observe_opening_count(commitment, 1));
+        assert_eq!(two, two_again);
+        assert_ne!(two, one);
+    }
+
+    #[test]
+    fn test_observe_salt() {
+        let salted = sample_after(|c| c.observe_salt(&[1; 32]));
+        let salted_again = sample_after(|c| c.observe_salt(&[1; 32]));
+        let resalted = sample_after(|c| c.observe_salt(&[2; 32]));
+        assert_eq!(salted, salted_again);
+        assert_ne!(salted, resalted);
+    }
+
+    #[test]
+    fn test_sorted_commitment_affects_permutation_challenge() {
+        let witness_commit = Hash::<F, u8, 32>::from([1; 32]);
+        let permutation_challenge = |sorted_commit: Hash<F, u8, 32>| {
+            sample_after(|c| {
+                c.observe(witness_commit);
+                c.observe_sorted_commitment(sorted_commit);
+            })
+        };
+        let first = permutation_challenge(Hash::from([2; 32]));
+        let first_again = permutation_challenge(Hash::from([2; 32]));
+        let other = permutation_challenge(Hash::from([3; 32]));
+        assert_eq!(first, first_again);
+        assert_ne!(first, other);
+    }
+
+    #[test]
+    fn test_proof_id_is_stable() {
+        let commitment_phase = |challenger: &mut Chal| {
+            challenger.observe(F::from_u8(1));
+            challenger.observe(Hash::<F, u8, 32>::from([2; 32]));
+            challenger.mark_commitments_done();
+        };
+
+        let mut first = challenger();
+        commitment_phase(&mut first);
+        let mut second = challenger();
+        commitment_phase(&mut second);
+        assert_eq!(first.proof_id(), second.proof_id());
+
+        // Openings observed after the boundary do not change the id.
+        let id = first.proof_id();
+        first.observe(F::from_u8(3));
+        let _: F = first.sample();
+        assert_eq!(first.proof_id(), id);
+
+        let mut other = challenger();
+        other.observe(F::from_u8(4));
+        other.mark_commitments_done();
+        assert_ne!(other.proof_id(), id);
+
+        // The id is kept apart from the output the next challenges are drawn from, which the
+        // flush leaves in the input buffer as its chaining value.
+        let mut marked = challenger();
+        commitment_phase(&mut marked);
+        let _: F = marked.sample();
+        assert_eq!(marked.inner.input_buffer().len(), 32);
+        assert_ne!(&marked.proof_id()[..], marked.inner.input_buffer());
+    }
+
+    #[test]
+    fn test_observe_and_check_cap() {
+        let cap = [Hash::<F, u8, 32>::from([1; 32]), Hash::from([2; 32])];
+
+        let mut matching = challenger();
+        assert_eq!(matching.observe_and_check_cap(&cap, 1), Ok(()));
+        let matching_sample: F = matching.sample();
+        let expected = sample_after(|c| {
+            c.observe_u32(2);
+            c.observe(cap[0]);
+            c.observe(cap[1]);
+        });
+        assert_eq!(matching_sample, expected);
+
+        // A mismatching cap is rejected without touching the transcript.
+        let mut mismatching = challenger();
+        assert_eq!(
+            mismatching.observe_and_check_cap(&cap, 2),
+            Err(CapError {
+                expected_log_height: 2,
+                actual_len: 2,
+            })
+        );
+
+        // A height too large for `2^height` to fit in a `usize` is rejected rather than wrapping.
+        assert_eq!(
+            challenger().observe_and_check_cap(&cap, 64),
+            Err(CapError {
+                expected_log_height: 64,
+                actual_len: 2,
+            })
+        );
+        let mismatching_sample: F = mismatching.sample();
+        assert_eq!(mismatching_sample, sample_after(|_| {}));
+    }
+
+    #[test]
+    fn test_observe_signed_beacon_binds_signature() {
+        let beacon = [0xab; 16];
+        let signed = sample_after(|c| c.observe_signed_beacon(&beacon, &[1; 64]));
+        let signed_again = sample_after(|c| c.observe_signed_beacon(&beacon, &[1; 64]));
+        let resigned = sample_after(|c| c.observe_signed_beacon(&beacon, &[2; 64]));
+        assert_eq!(signed, signed_again);
+        assert_ne!(signed, resigned);
+    }
+
+    #[test]
+    fn test_sample_base_in_extension() {
+        let mut challenger = challenger();
+        let mut reference = challenger.clone();
+
+        let embedded: EF = challenger.sample_base_in_extension();
+        let coeffs = embedded.as_basis_coefficients_slice();
+        assert!(coeffs[1..].iter().all(|&coeff| coeff == F::ZERO));
+
+        let base: F = reference.sample();
+        assert_eq!(coeffs[0], base);
+    }
+
+    #[cfg(feature = "debug-transcript")]
+    #[test]
+    fn test_observe_blinded_commitment_with_seed() {
+        let commit = Hash::<F, u8, 32>::from([5; 32]);
+        let seeded = sample_after(|c| c.observe_blinded_commitment_with_seed(commit, 1));
+        let seeded_again = sample_after(|c| c.observe_blinded_commitment_with_seed(commit, 1));
+        let reseeded = sample_after(|c| c.observe_blinded_commitment_with_seed(commit, 2));
+        assert_eq!(seeded, seeded_again);
+        assert_ne!(seeded, reseeded);
+    }
+
+    #[test]
+    fn test_sample_accumulation_challenge_order() {
+        let acc_commit = Hash::<F, u8, 32>::from([1; 32]);
+        let new_commit = Hash::<F, u8, 32>::from([2; 32]);
+        let folding: EF = challenger().sample_accumulation_challenge(acc_commit, new_commit);
+        let folding_again: EF = challenger().sample_accumulation_challenge(acc_commit, new_commit);
+        let swapped: EF = challenger().sample_accumulation_challenge(new_commit, acc_commit);
+        assert_eq!(folding, folding_again);
+        assert_ne!(folding, swapped);
+    }
+
+    #[test]
+    fn test_observe_commitments_parallel_matches_tree_hash() {
+        let roots: Vec<Hash<F, u8, 32>> = (0..3 * COMMITMENT_CHUNK_SIZE + 5)
+            .map(|i| Hash::from([i as u8; 32]))
+            .collect();
+
+        // Serial reference: hash each chunk into a leaf, then hash the leaves together.
+        let leaves: Vec<[u8; 32]> = roots
+            .chunks(COMMITMENT_CHUNK_SIZE)
+            .map(|chunk| Sha256.hash_iter(chunk.iter().flat_map(|&root| root)))
+            .collect();
+        let combined = Sha256.hash_iter(leaves.into_iter().flatten());
+        let expected = sample_after(|c| {
+            c.inner.observe(tag::PARALLEL_COMMITMENTS);
+            c.inner.observe_slice(&combined);
+        });
+
+        assert_eq!(
+            sample_after(|c| c.observe_commitments_parallel(&roots)),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_sample_permutation_challenges() {
+        let witness_commit = Hash::<F, u8, 32>::from([1; 32]);
+        let (beta, gamma): (EF, EF) = challenger().sample_permutation_challenges(witness_commit);
+
+        let mut reference = challenger();
+        reference.observe(witness_commit);
+        let expected = CanSample::<EF>::sample_vec(&mut reference, 2);
+        assert_eq!(expected, [beta, gamma]);
+
+        let (other_beta, _): (EF, EF) =
+            challenger().sample_permutation_challenges(Hash::from([2; 32]));
+        assert_ne!(beta, other_beta);
+    }
+
+    #[test]
+    fn test_dump_inner_state() {
+        let run = || {
+            let mut challenger = challenger();
+            challenger.observe(F::from_u8(1));
+            let _: F = challenger.sample();
+            challenger
+        };
+        let dump = run().dump_inner_state();
+        assert_eq!(dump, run().dump_inner_state());
+
+        // After a flush only the 32 byte chaining value is left in the input buffer, and sampling
+        // consumed a multiple of four of the 32 output bytes.
+        assert_eq!(dump[..8], 32u64.to_le_bytes());
+        let output_len = u64::from_le_bytes(dump[40..48].try_into().unwrap()) as usize;
+        assert!(output_len < 32 && output_len % 4 == 0);
+        assert_eq!(dump.len(), 48 + output_len);
+    }
+
+    #[test]
+    fn test_observe_column_bounds() {
+        let bounds = sample_after(|c| c.observe_column_bounds(&[4, 8]));
+        let bounds_again = sample_after(|c| c.observe_column_bounds(&[4, 8]));
+        let other_bounds = sample_after(|c| c.observe_column_bounds(&[4, 16]));
+        assert_eq!(bounds, bounds_again);
+        assert_ne!(bounds, other_bounds);
+    }
+
+    #[test]
+    fn test_observe_constraint_degree() {
+        let quotient_commit = Hash::<F, u8, 32>::from([3; 32]);
+        let with_degree = |max_degree| {
+            sample_after(|c| {
+                c.observe_constraint_degree(max_degree);
+                c.observe(quotient_commit);
+            })
+        };
+        assert_eq!(with_degree(3), with_degree(3));
+        assert_ne!(with_degree(3), with_degree(4));
+    }
+
+    #[test]
+    fn test_sample_cross_round_batch_challenge_order() {
+        let rounds = [Hash::<F, u8, 32>::from([1; 32]), Hash::from([2; 32])];
+        let reordered = [rounds[1], rounds[0]];
+        let batch: EF = challenger().sample_cross_round_batch_challenge(&rounds);
+        let batch_again: EF = challenger().sample_cross_round_batch_challenge(&rounds);
+        let reordered_batch: EF = challenger().sample_cross_round_batch_challenge(&reordered);
+        assert_eq!(batch, batch_again);
+        assert_ne!(batch, reordered_batch);
+    }
+
+    #[test]
+    fn test_observe_eval_points_binds_order() {
+        let points = [EF::from_u8(1), EF::from_u8(2)];
+        let permuted = [points[1], points[0]];
+        let opened = sample_after(|c| c.observe_eval_points(&points));
+        let opened_again = sample_after(|c| c.observe_eval_points(&points));
+        let opened_permuted = sample_after(|c| c.observe_eval_points(&permuted));
+        assert_eq!(opened, opened_again);
+        assert_ne!(opened, opened_permuted);
+    }
+
+    #[test]
+    fn test_observe_recursion_depth() {
+        let mut within_limit = challenger();
+        assert_eq!(within_limit.observe_recursion_depth(2, 2), Ok(()));
+        let sample: F = within_limit.sample();
+        assert_eq!(sample, sample_after(|c| c.observe_u32(2)));
+
+        let mut over_limit = challenger();
+        assert_eq!(
+            over_limit.observe_recursion_depth(3, 2),
+            Err(DepthError {
+                depth: 3,
+                max_depth: 2,
+            })
+        );
+    }
+
+    #[test]
+    fn test_observe_versioned_commitment() {
+        let commitment = Hash::<F, u8, 32>::from([4; 32]);
+        let v1 = sample_after(|c| c.observe_versioned_commitment(1, commitment));
+        let v1_again = sample_after(|c| c.observe_versioned_commitment(1, commitment));
+        let v2 = sample_after(|c| c.observe_versioned_commitment(2, commitment));
+        assert_eq!(v1, v1_again);
+        assert_ne!(v1, v2);
+
+        // A version equal to another message's tag does not reproduce that message.
+        let versioned =
+            sample_after(|c| c.observe_versioned_commitment(tag::BLINDING_COMMITMENT, commitment));
+        let blinding = sample_after(|c| c.observe_blinding_commitment(commitment));
+        assert_ne!(versioned, blinding);
+    }
+
+    #[test]
+    fn test_sample_basefold_rounds() {
+        let fold = |round_commits: &[Hash<F, u8, 32>]| {
+            let mut challenger = challenger();
+            round_commits
+                .iter()
+                .map(|&commit| challenger.sample_basefold_round(commit))
+                .collect::<Vec<EF>>()
+        };
+        let commits = [1, 2, 3].map(|i| Hash::<F, u8, 32>::from([i; 32]));
+        let mut altered = commits;
+        altered[1] = Hash::from([4; 32]);
+
+        let challenges = fold(&commits);
+        let altered_challenges = fold(&altered);
+        // Each challenge depends on its own round commitment, and on every earlier one.
+        assert_eq!(challenges[0], altered_challenges[0]);
+        assert_ne!(challenges[1], altered_challenges[1]);
+        assert_ne!(challenges[2], altered_challenges[2]);
+        assert_eq!(challenges, fold(&commits));
+    }
+
+    #[test]
+    fn test_observe_claimed_sum_affects_first_round() {
+        let round_poly_evals = [EF::from_u8(1), EF::from_u8(2)];
+        let first_round = |claimed: EF| {
+            let mut challenger = challenger();
+            challenger.observe_claimed_sum(claimed);
+            challenger.sample_sumcheck_round(&round_poly_evals)
+        };
+        assert_eq!(first_round(EF::from_u8(3)), first_round(EF::from_u8(3)));
+        assert_ne!(first_round(EF::from_u8(3)), first_round(EF::from_u8(4)));
+    }
+
+    #[test]
+    fn test_deferred_sampling_matches_eager() {
+        let commitment = Hash::<F, u8, 32>::from([6; 32]);
+        let eager: EF = challenger().observe_then_sample(commitment);
+
+        let mut pipelined = challenger();
+        let handle = pipelined.observe_deferred(commitment);
+        pipelined.observe(F::from_u8(1));
+        let _: F = pipelined.sample();
+        let deferred: EF = handle.sample();
+        assert_eq!(deferred, eager);
+    }
+
+    #[test]
+    fn test_preprocessed_commitment_affects_later_challenges() {
+        let trace_commit = Hash::<F, u8, 32>::from([1; 32]);
+        let challenges = |prep_commit: Hash<F, u8, 32>| {
+            let mut challenger = challenger();
+            challenger.observe_preprocessed_commitment(prep_commit);
+            challenger.observe(trace_commit);
+            CanSample::<EF>::sample_vec(&mut challenger, 3)
+        };
+        let first = challenges(Hash::from([2; 32]));
+        let other = challenges(Hash::from([3; 32]));
+        assert_eq!(first, challenges(Hash::from([2; 32])));
+        assert!(first.iter().zip(&other).all(|(a, b)| a != b));
+    }
+
+    #[test]
+    fn test_sample_then_observe_aux() {
+        let aux_commit = Hash::<F, u8, 32>::from([1; 32]);
+        let next: EF = challenger().sample_then_observe_aux(aux_commit);
+
+        // The two phases, run by hand with the first challenge peeked ahead of time.
+        let mut reference = challenger();
+        let peeked: EF = reference.peek_sample();
+        let aux_challenge: EF = reference.sample();
+        assert_eq!(aux_challenge, peeked);
+        let expected: EF = reference.observe_then_sample(aux_commit);
+        assert_eq!(next, expected);
+
+        let other_next: EF = challenger().sample_then_observe_aux(Hash::from([2; 32]));
+        assert_ne!(next, other_next);
+    }
+
+    #[test]
+    fn test_observe_extension_degree() {
+        let mut matching = challenger();
+        assert_eq!(matching.observe_extension_degree::<EF>(4), Ok(()));
+        let sample: F = matching.sample();
+        assert_eq!(sample, sample_after(|c| c.observe_u32(4)));
+
+        let mut mismatching = challenger();
+        assert_eq!(
+            mismatching.observe_extension_degree::<EF>(5),
+            Err(FieldMismatch {
+                expected_degree: 4,
+                claimed_degree: 5,
+            })
+        );
+    }
+
+    #[test]
+    fn test_output_commitment_affects_later_challenges() {
+        let challenges = |output_commit: Hash<F, u8, 32>| {
+            let mut challenger = challenger();
+            challenger.observe_output_commitment(output_commit);
+            CanSample::<EF>::sample_vec(&mut challenger, 2)
+        };
+        let first = challenges(Hash::from([1; 32]));
+        let other = challenges(Hash::from([2; 32]));
+        assert_eq!(first, challenges(Hash::from([1; 32])));
+        assert!(first.iter().zip(&other).all(|(a, b)| a != b));
+    }
+
+    #[test]
+    fn test_sample_aggregation_node_challenge() {
+        let children = [[1; 32], [2; 32]];
+        let other_children = [[1; 32], [3; 32]];
+        let node: EF = challenger().sample_aggregation_node_challenge(&children);
+        let node_again: EF = challenger().sample_aggregation_node_challenge(&children);
+        let fewer_children: EF = challenger().sample_aggregation_node_challenge(&children[..1]);
+        let other_node: EF = challenger().sample_aggregation_node_challenge(&other_children);
+        assert_eq!(node, node_again);
+        assert_ne!(node, fewer_children);
+        assert_ne!(node, other_node);
+    }
+
+    #[test]
+    fn test_observe_dft_algorithm() {
+        let radix_2 = sample_after(|c| c.observe_dft_algorithm(0));
+        let radix_2_again = sample_after(|c| c.observe_dft_algorithm(0));
+        let bluestein = sample_after(|c| c.observe_dft_algorithm(2));
+        assert_eq!(radix_2, radix_2_again);
+        assert_ne!(radix_2, bluestein);
+
+        // An id equal to another message's tag does not reproduce that message.
+        let commitment = Hash::<F, u8, 32>::from([4; 32]);
+        let tagged_id = sample_after(|c| {
+            c.observe_dft_algorithm(tag::PREPROCESSED_COMMITMENT);
+            c.observe(commitment);
+        });
+        let preprocessed = sample_after(|c| c.observe_preprocessed_commitment(commitment));
+        assert_ne!(tagged_id, preprocessed);
+    }
+
+    #[test]
+    fn test_observe_segment_boundary() {
+        let state = [F::from_u8(1), F::from_u8(2)];
+        let other_state = [F::from_u8(1), F::from_u8(3)];
+        let chained = sample_after(|c| c.observe_segment_boundary(&state, &state));
+        let chained_again = sample_after(|c| c.observe_segment_boundary(&state, &state));
+        let mismatched = sample_after(|c| c.observe_segment_boundary(&state, &other_state));
+        assert_eq!(chained, chained_again);
+        assert_ne!(chained, mismatched);
+    }
+
+    #[test]
+    fn test_sample_array_with_trace() {
+        let mut challenger = Chal::from_hasher(vec![0], Sha256);
+        let mut reference = challenger.clone();
+
+        let (samples, trace) = challenger.sample_array_with_trace::<4, F>();
+        let expected = [397385757, 85041926, 859256950, 997722668].map(F::from_u32);
+        let reference_samples: [F; 4] = reference.sample_array();
+        assert_eq!(samples, expected);
+        assert_eq!(samples, reference_samples);
+        // Five candidates were drawn; the fourth, 0x78901d3f, exceeds the modulus and was rejected.
+        assert_eq!(
+            trace,
+            [
+                29, 160, 175, 23, 6, 163, 17, 133, 118, 56, 55, 179, 63, 29, 144, 120, 44, 10, 120,
+                187,
+            ]
+        );
+    }
+
+    #[cfg(feature = "debug-transcript")]
+    #[test]
+    fn test_observe_prover_version() {
+        let v1 = sample_after(|c| c.observe_prover_version("0.1.0"));
+        let v1_again = sample_after(|c| c.observe_prover_version("0.1.0"));
+        let v2 = sample_after(|c| c.observe_prover_version("0.2.0"));
+        assert_eq!(v1, v1_again);
+        assert_ne!(v1, v2);
+    }
+}